@@ -1,65 +1,98 @@
+use std::env;
 use std::fs;
 
-fn expanded_distance(start: usize, end: usize, expanded: &Vec<usize>) -> usize {
-    let low = start.min(end);
-    let high = start.max(end);
-    let mut dist = high - low;
-    for x in expanded {
-        if low < *x && *x < high {
-            dist += 1000000 - 1;  // Set to 1 for part 1
-        }
+// Where each row (or column) ends up once every empty one is replaced by `factor` of them.
+// A factor of 0 makes empty lines disappear. Positions can get bigger than a u64 when the factor is huge.
+fn expanded_positions(is_empty: &[bool], factor: u64) -> Vec<u128> {
+    let mut positions = vec![];
+    let mut position: u128 = 0;
+    for empty in is_empty {
+        positions.push(position);
+        position += if *empty { factor as u128 } else { 1 };
     }
-    return dist;
+    return positions;
 }
 
-fn main() {
-    let content = fs::read_to_string("./src/input11.txt").unwrap();
-    let grid: Vec<_> = content.lines().map(|l| l.as_bytes()).collect();
+// Sum of |a - b| over every pair. Each coordinate is bigger than all the ones before it once sorted,
+// so it adds itself once per earlier coordinate and takes away their sum.
+fn sum_pairwise_distances(coords: &mut Vec<u128>) -> u128 {
+    coords.sort();
+
+    let mut sum = 0;
+    let mut prefix_sum = 0;
+    for (i, coord) in coords.iter().enumerate() {
+        sum += coord * i as u128 - prefix_sum;
+        prefix_sum += coord;
+    }
+    return sum;
+}
+
+fn sum_galaxy_distances(grid: &Vec<&[u8]>, factor: u64) -> u128 {
     let height = grid.len();
     let width = grid[0].len();
 
-    let mut sum = 0;
+    let empty_rows: Vec<bool> = (0..height).map(|r| (0..width).all(|c| grid[r][c] == b'.')).collect();
+    let empty_cols: Vec<bool> = (0..width).map(|c| (0..height).all(|r| grid[r][c] == b'.')).collect();
 
-    let mut empty_rows: Vec<usize> = vec![];
-    let mut empty_cols: Vec<usize> = vec![];
+    let row_positions = expanded_positions(&empty_rows, factor);
+    let col_positions = expanded_positions(&empty_cols, factor);
 
+    // Manhattan distance splits into rows and columns, which can be summed separately.
+    let mut rows = vec![];
+    let mut cols = vec![];
     for r in 0..height {
-        if (0..width).all(|c| grid[r][c] == b'.') {
-            empty_rows.push(r);
+        for c in 0..width {
+            if grid[r][c] == b'#' {
+                rows.push(row_positions[r]);
+                cols.push(col_positions[c]);
+            }
         }
     }
 
-    for c in 0..width {
-        if (0..height).all(|r| grid[r][c] == b'.') {
-            empty_cols.push(c);
-        }
-    }
+    return sum_pairwise_distances(&mut rows) + sum_pairwise_distances(&mut cols);
+}
 
-    for r1 in 0..height {
-        for c1 in 0..width {
-            let byte1 = grid[r1][c1];
-            if byte1 != b'#' {
-                continue;
-            }
-            for r2 in 0..height {
-                for c2 in 0..width {
-                    let byte2 = grid[r2][c2];
-                    if byte2 != b'#' {
-                        continue;
-                    }
-                    if r1 == r2 && c1 == c2 {
-                        continue;
-                    }
-                    if r2 < r1 || (r2 == r1 && c2 < c1) {
-                        // Include each pair exactly once
-                        continue;
-                    }
-                    sum += expanded_distance(r1, r2, &empty_rows) + 
-                        expanded_distance(c1, c2, &empty_cols)
-                }
-            }
+// Usage: day11 [expansion factor]
+// Without a factor, prints both parts: empty rows and columns become 2 of them in part 1 and 1000000 in part 2.
+fn main() {
+    let content = fs::read_to_string("./src/input11.txt").unwrap();
+    let grid: Vec<_> = content.lines().map(|l| l.as_bytes()).collect();
+
+    match env::args().nth(1) {
+        Some(factor) => println!("Sum: {}", sum_galaxy_distances(&grid, factor.parse().unwrap())),
+        None => {
+            println!("Sum: {}", sum_galaxy_distances(&grid, 2));
+            println!("Sum: {}", sum_galaxy_distances(&grid, 1000000));
         }
     }
+}
 
-    println!("Sum: {}", sum);
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "...#......
+.......#..
+#.........
+..........
+......#...
+.#........
+.........#
+..........
+.......#..
+#...#.....";
+
+    #[test]
+    fn test_sum_galaxy_distances() {
+        let grid: Vec<_> = EXAMPLE.lines().map(|l| l.as_bytes()).collect();
+
+        assert_eq!(sum_galaxy_distances(&grid, 2), 374);
+        assert_eq!(sum_galaxy_distances(&grid, 10), 1030);
+        assert_eq!(sum_galaxy_distances(&grid, 100), 8410);
+
+        // Each pair crosses 82 empty lines in total, so every extra line per empty one adds 82.
+        assert_eq!(sum_galaxy_distances(&grid, 1), 292);
+        assert_eq!(sum_galaxy_distances(&grid, 0), 210);
+        assert_eq!(sum_galaxy_distances(&grid, u64::MAX), 292 + 82 * (u64::MAX as u128 - 1));
+    }
+}
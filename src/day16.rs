@@ -1,5 +1,10 @@
 use std::cmp;
+use std::env;
 use std::fs;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use lazy_static::lazy_static;
 
 type Grid<T> = Vec<Vec<T>>;
 
@@ -40,19 +45,14 @@ fn step_in_direction(point: (usize, usize), direction: Direction, width: usize,
     return None;
 }
 
-struct Context {
+struct Context<'a> {
     width: usize,
     height: usize,
-    grid: Grid<u8>,
+    grid: &'a Grid<u8>,
+    elements: &'a Elements,
     energy: Grid<u8>
 }
 
-fn fire_laser_forward(context: &mut Context, direction: Direction, point: (usize, usize)) {
-    if let Some(new_point) = step_in_direction(point, direction, context.width, context.height) {
-        fire_laser(context, direction, new_point);
-    }
-}
-
 fn mark_visited(context: &mut Context, direction: Direction, point: (usize, usize)) -> bool {
     let mask = direction_to_mask(direction);
     let (x, y) = point;
@@ -63,41 +63,94 @@ fn mark_visited(context: &mut Context, direction: Direction, point: (usize, usiz
     return false;
 }
 
+// An optical element: given the direction a beam enters its tile in, the directions the beam leaves in.
+type Element = fn(Direction) -> Vec<Direction>;
+
+type Elements = HashMap<u8, Element>;
+
+fn empty_space(direction: Direction) -> Vec<Direction> {
+    return vec![direction];
+}
+
+fn backslash_mirror((dx, dy): Direction) -> Vec<Direction> {
+    return vec![(dy, dx)];
+}
+
+fn slash_mirror((dx, dy): Direction) -> Vec<Direction> {
+    return vec![(-dy, -dx)];
+}
+
+fn horizontal_splitter(direction: Direction) -> Vec<Direction> {
+    return if direction.1 != 0 { vec![(1, 0), (-1, 0)] } else { vec![direction] };
+}
+
+fn vertical_splitter(direction: Direction) -> Vec<Direction> {
+    return if direction.0 != 0 { vec![(0, 1), (0, -1)] } else { vec![direction] };
+}
+
+fn absorber(_direction: Direction) -> Vec<Direction> {
+    return vec![];
+}
+
+fn four_way_splitter(_direction: Direction) -> Vec<Direction> {
+    return vec![(0, 1), (0, -1), (1, 0), (-1, 0)];
+}
+
+// One-way mirrors shaped like the pipes in day 10. Only the inside of the corner is silvered:
+// a beam heading into it turns out along the other arm, and anything else passes straight through.
+fn f_mirror(direction: Direction) -> Vec<Direction> {
+    return match direction {
+        (-1, 0) => vec![(0, 1)],
+        (0, -1) => vec![(1, 0)],
+        _ => vec![direction],
+    };
+}
+
+fn j_mirror(direction: Direction) -> Vec<Direction> {
+    return match direction {
+        (1, 0) => vec![(0, -1)],
+        (0, 1) => vec![(-1, 0)],
+        _ => vec![direction],
+    };
+}
+
+lazy_static!{
+    static ref ELEMENTS: Elements = HashMap::from_iter(vec![
+        (b'.', empty_space as Element),
+        (b'\\', backslash_mirror),
+        (b'/', slash_mirror),
+        (b'-', horizontal_splitter),
+        (b'|', vertical_splitter),
+        (b'#', absorber),
+        (b'+', four_way_splitter),
+        (b'F', f_mirror),
+        (b'J', j_mirror),
+    ]);
+}
+
+// Where a beam goes after entering a tile with `c` on it while travelling in `direction`.
+fn outgoing_directions(elements: &Elements, c: u8, direction: Direction) -> Vec<Direction> {
+    match elements.get(&c) {
+        Some(element) => element(direction),
+        None => panic!("Invalid character {}", c as char),
+    }
+}
+
+// Beams split into more beams, so keep the ones still to be followed on a stack rather than recursing.
 fn fire_laser(context: &mut Context, direction: Direction, point: (usize, usize)) {
-    let (x, y) = point;
-    let (dx, dy) = direction;
-    let c = context.grid[y][x];
-    if !mark_visited(context, direction, point) {
-        return;
-    }
-
-    match c {
-        b'\\' => {
-            fire_laser_forward(context, (dy, dx), point);
-        },
-        b'/' => {
-            fire_laser_forward(context, (-dy, -dx), point);
-        },
-        b'-' => {
-            if dy != 0 {
-                fire_laser_forward(context, (1, 0), point);
-                fire_laser_forward(context, (-1, 0), point);
-            } else {
-                fire_laser_forward(context, direction, point);
-            }
-        },
-        b'|' => {
-            if dx != 0 {
-                fire_laser_forward(context, (0, 1), point);
-                fire_laser_forward(context, (0, -1), point);
-            } else {
-                fire_laser_forward(context, direction, point);
+    let mut beams = vec![(direction, point)];
+
+    while let Some((direction, point)) = beams.pop() {
+        if !mark_visited(context, direction, point) {
+            continue;
+        }
+
+        let (x, y) = point;
+        for new_direction in outgoing_directions(context.elements, context.grid[y][x], direction) {
+            if let Some(new_point) = step_in_direction(point, new_direction, context.width, context.height) {
+                beams.push((new_direction, new_point));
             }
-        },
-        b'.' => {
-            fire_laser_forward(context, direction, point);
         }
-        c => panic!("Invalid character {c}")
     }
 }
 
@@ -111,11 +164,85 @@ fn energy_grid_to_string(grid: &Grid<u8>) -> String {
     return strs.join("\n");
 }
 
+fn mask_to_arrow(mask: u8) -> u8 {
+    match mask {
+        DOWN => b'v',
+        UP => b'^',
+        RIGHT => b'>',
+        LEFT => b'<',
+        _ => panic!("Not a single direction")
+    }
+}
+
+// The contraption with the beams drawn over its empty tiles, like the puzzle does: an arrow where one beam passes
+// through, or the number of beams where several cross. Mirrors and splitters are left as they are.
+fn beams_to_string(grid: &Grid<u8>, energy: &Grid<u8>) -> String {
+    let rows: Vec<String> = grid.iter().zip(energy.iter()).map(|(row, energy_row)| {
+        let bytes: Vec<u8> = row.iter().zip(energy_row.iter()).map(|(c, mask)| {
+            match (*c, mask.count_ones()) {
+                (b'.', 0) => b'.',
+                (b'.', 1) => mask_to_arrow(*mask),
+                (b'.', n) => b'0' + n as u8,
+                (c, _) => c,
+            }
+        }).collect();
+        return String::from_utf8(bytes).unwrap();
+    }).collect();
+
+    return rows.join("\n");
+}
+
+// Each tile becomes a square of this many pixels, so the images are big enough to look at.
+const PIXELS_PER_TILE: usize = 4;
+
+// Brightness goes up with the number of beams through a tile. Mirrors and splitters show up a little even when dark.
+fn tile_brightness(c: u8, mask: u8) -> u8 {
+    let base = if c == b'.' { 0 } else { 40 };
+    return base + 50 * mask.count_ones() as u8;
+}
+
+// Yellow for one beam, through orange, to red for four. Mirrors and splitters are grey, or white when lit up.
+fn tile_color(c: u8, mask: u8) -> [u8; 3] {
+    return match (c, mask.count_ones()) {
+        (b'.', 0) => [0, 0, 0],
+        (b'.', n) => [255, 255 - 70 * (n as u8 - 1), 0],
+        (_, 0) => [90, 90, 90],
+        (_, _) => [255, 255, 255],
+    };
+}
+
+// Binary PPM (P6) or PGM (P5) image, with `pixel` giving the bytes for each tile.
+fn netpbm_image(grid: &Grid<u8>, energy: &Grid<u8>, magic: &str, pixel: impl Fn(u8, u8) -> Vec<u8>) -> Vec<u8> {
+    let height = grid.len();
+    let width = grid[0].len();
+
+    let mut image = format!("{}\n{} {}\n255\n", magic, width * PIXELS_PER_TILE, height * PIXELS_PER_TILE).into_bytes();
+    for (row, energy_row) in grid.iter().zip(energy.iter()) {
+        let pixel_row: Vec<u8> = row.iter().zip(energy_row.iter())
+            .flat_map(|(c, mask)| pixel(*c, *mask).repeat(PIXELS_PER_TILE))
+            .collect();
+        for _ in 0..PIXELS_PER_TILE {
+            image.extend_from_slice(&pixel_row);
+        }
+    }
+
+    return image;
+}
+
+fn ppm_image(grid: &Grid<u8>, energy: &Grid<u8>) -> Vec<u8> {
+    return netpbm_image(grid, energy, "P6", |c, mask| tile_color(c, mask).to_vec());
+}
+
+fn pgm_image(grid: &Grid<u8>, energy: &Grid<u8>) -> Vec<u8> {
+    return netpbm_image(grid, energy, "P5", |c, mask| vec![tile_brightness(c, mask)]);
+}
+
 fn count_energized(grid: &Grid<u8>) -> usize {
     return grid.iter().map(|row| row.iter().filter(|c| **c != 0).count()).sum();
 }
 
-fn fire_laser_and_count(grid: &Grid<u8>, start_direction: Direction, start_point: (usize, usize)) -> usize {
+// Which directions beams pass through each tile in, as a bitmask.
+fn fire_laser_from(grid: &Grid<u8>, elements: &Elements, start_direction: Direction, start_point: (usize, usize)) -> Grid<u8> {
     let height = grid.len();
     let width = grid[0].len();
 
@@ -124,53 +251,443 @@ fn fire_laser_and_count(grid: &Grid<u8>, start_direction: Direction, start_point
     let mut context = Context {
         width: width,
         height: height,
-        grid: grid.clone(),
+        grid: grid,
+        elements: elements,
         energy: energy,
     };
 
     fire_laser(&mut context, start_direction, start_point);
 
-    return count_energized(&context.energy);
+    return context.energy;
 }
 
-fn part2() {
-    let content = fs::read_to_string("./src/input16.txt").unwrap();
-    let grid: Grid<_> = content.lines().map(|l| l.bytes().collect()).collect();
+fn direction_name(direction: Direction) -> &'static str {
+    match direction {
+        (0, 1) => "down",
+        (0, -1) => "up",
+        (1, 0) => "right",
+        (-1, 0) => "left",
+        _ => panic!("Invalid direction")
+    }
+}
+
+// Every way a beam can enter the contraption from outside.
+fn edge_starts(width: usize, height: usize) -> Vec<(Direction, (usize, usize))> {
+    let mut starts = vec![];
+    for y in 0..height {
+        starts.push(((1, 0), (0, y)));
+        starts.push(((-1, 0), (width - 1, y)));
+    }
+    for x in 0..width {
+        starts.push(((0, 1), (x, 0)));
+        starts.push(((0, -1), (x, height - 1)));
+    }
+    return starts;
+}
+
+// A set of tiles, one bit each.
+type TileSet = Vec<u64>;
+
+fn tile_set_insert(set: &mut TileSet, tile: usize) {
+    set[tile / 64] |= 1 << (tile % 64);
+}
+
+fn tile_set_union(set: &mut TileSet, other: &TileSet) {
+    for (a, b) in set.iter_mut().zip(other.iter()) {
+        *a |= b;
+    }
+}
+
+fn tile_set_len(set: &TileSet) -> usize {
+    return set.iter().map(|word| word.count_ones() as usize).sum();
+}
 
+// A beam's state is the tile it's on and the direction it came in, numbered (y * width + x) * 4 + direction bit.
+fn beam_state(width: usize, direction: Direction, point: (usize, usize)) -> usize {
+    let (x, y) = point;
+    return (y * width + x) * 4 + direction_to_mask(direction).trailing_zeros() as usize;
+}
+
+fn state_tile(state: usize) -> usize {
+    return state / 4;
+}
+
+fn beam_successors(grid: &Grid<u8>, elements: &Elements) -> Vec<Vec<usize>> {
     let height = grid.len();
     let width = grid[0].len();
 
-    let mut max_energized = 0;
+    let mut successors = vec![vec![]; width * height * 4];
     for y in 0..height {
-        max_energized = cmp::max(max_energized, fire_laser_and_count(&grid, (1, 0), (0, y)));
-        max_energized = cmp::max(max_energized, fire_laser_and_count(&grid, (-1, 0), (width-1, y)));
+        for x in 0..width {
+            for direction in [(0, 1), (0, -1), (1, 0), (-1, 0)] {
+                let state = beam_state(width, direction, (x, y));
+                for new_direction in outgoing_directions(elements, grid[y][x], direction) {
+                    if let Some(new_point) = step_in_direction((x, y), new_direction, width, height) {
+                        successors[state].push(beam_state(width, new_direction, new_point));
+                    }
+                }
+            }
+        }
     }
-    for x in 0..width {
-        max_energized = cmp::max(max_energized, fire_laser_and_count(&grid, (0, 1), (x, 0)));
-        max_energized = cmp::max(max_energized, fire_laser_and_count(&grid, (0, -1), (x, height-1)));
+
+    return successors;
+}
+
+// Tarjan's algorithm, with an explicit stack of (vertex, next successor to look at) in place of recursion.
+// Components come out in reverse topological order: everything a component leads to comes out before it.
+fn strongly_connected_components(successors: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    const UNVISITED: usize = usize::MAX;
+
+    let n = successors.len();
+    let mut index = vec![UNVISITED; n];
+    let mut low = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let mut stack = vec![];
+    let mut next_index = 0;
+    let mut components = vec![];
+
+    for root in 0..n {
+        if index[root] != UNVISITED {
+            continue;
+        }
+
+        index[root] = next_index;
+        low[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        let mut call_stack = vec![(root, 0)];
+        while let Some((v, i)) = call_stack.pop() {
+            if i < successors[v].len() {
+                call_stack.push((v, i + 1));
+
+                let w = successors[v][i];
+                if index[w] == UNVISITED {
+                    index[w] = next_index;
+                    low[w] = next_index;
+                    next_index += 1;
+                    stack.push(w);
+                    on_stack[w] = true;
+                    call_stack.push((w, 0));
+                } else if on_stack[w] {
+                    low[v] = cmp::min(low[v], index[w]);
+                }
+                continue;
+            }
+
+            if low[v] == index[v] {
+                let mut component = vec![];
+                loop {
+                    let w = stack.pop().unwrap();
+                    on_stack[w] = false;
+                    component.push(w);
+                    if w == v {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+
+            if let Some((parent, _)) = call_stack.last() {
+                low[*parent] = cmp::min(low[*parent], low[v]);
+            }
+        }
     }
 
-    println!("{max_energized}");
+    return components;
+}
+
+// Answers "how many tiles get energized from here?" for many starts, sharing the work between them.
+//
+// Beam states are condensed into strongly connected components, which form a DAG. Most components are a single state
+// with a single way out, so the energized tiles are only stored for the rest (loops and splits), and the chains
+// between them are walked on demand.
+struct MemoizedTracer {
+    width: usize,
+    num_tiles: usize,
+    state_to_component: Vec<usize>,
+    components: Vec<Vec<usize>>,
+    component_successors: Vec<Vec<usize>>,
+    // Only filled in for components that aren't part of a chain.
+    energized: Vec<Option<TileSet>>,
+}
+
+impl MemoizedTracer {
+    fn new(grid: &Grid<u8>, elements: &Elements) -> MemoizedTracer {
+        let width = grid[0].len();
+        let num_tiles = width * grid.len();
+
+        let successors = beam_successors(grid, elements);
+        let components = strongly_connected_components(&successors);
+
+        let mut state_to_component = vec![0; successors.len()];
+        for (i, component) in components.iter().enumerate() {
+            for state in component.iter() {
+                state_to_component[*state] = i;
+            }
+        }
+
+        let component_successors: Vec<Vec<usize>> = components.iter().enumerate().map(|(i, component)| {
+            let mut next: Vec<usize> = component.iter()
+                .flat_map(|state| successors[*state].iter().map(|s| state_to_component[*s]))
+                .filter(|c| *c != i)
+                .collect();
+            next.sort();
+            next.dedup();
+            return next;
+        }).collect();
+
+        let mut tracer = MemoizedTracer {
+            width: width,
+            num_tiles: num_tiles,
+            state_to_component: state_to_component,
+            components: components,
+            component_successors: component_successors,
+            energized: vec![],
+        };
+
+        // Components are in reverse topological order, so everything a component leads to is already done.
+        for i in 0..tracer.components.len() {
+            let energized = if tracer.is_chain(i) {
+                None
+            } else {
+                let mut set = tracer.empty_tile_set();
+                for state in tracer.components[i].iter() {
+                    tile_set_insert(&mut set, state_tile(*state));
+                }
+                for next in tracer.component_successors[i].iter() {
+                    tracer.collect_energized(*next, &mut set);
+                }
+                Some(set)
+            };
+            tracer.energized.push(energized);
+        }
+
+        return tracer;
+    }
+
+    fn empty_tile_set(&self) -> TileSet {
+        return vec![0; (self.num_tiles + 63) / 64];
+    }
+
+    fn is_chain(&self, component: usize) -> bool {
+        return self.components[component].len() == 1 && self.component_successors[component].len() <= 1;
+    }
+
+    fn collect_energized(&self, component: usize, set: &mut TileSet) {
+        let mut component = component;
+        while self.is_chain(component) {
+            tile_set_insert(set, state_tile(self.components[component][0]));
+            match self.component_successors[component].first() {
+                Some(next) => component = *next,
+                None => return,
+            }
+        }
+
+        tile_set_union(set, self.energized[component].as_ref().unwrap());
+    }
+
+    fn count_energized(&self, direction: Direction, point: (usize, usize)) -> usize {
+        let mut set = self.empty_tile_set();
+        self.collect_energized(self.state_to_component[beam_state(self.width, direction, point)], &mut set);
+        return tile_set_len(&set);
+    }
+}
+
+// The edge start that energizes the most tiles, and how many it energizes. The tracer shares work between starts,
+// and the starts are shared out between threads.
+fn best_edge_start(grid: &Grid<u8>, elements: &Elements, num_threads: usize) -> (usize, Direction, (usize, usize)) {
+    let height = grid.len();
+    let width = grid[0].len();
+
+    let tracer = MemoizedTracer::new(grid, elements);
+    let starts = edge_starts(width, height);
+    let next_start = AtomicUsize::new(0);
+
+    // Ties go to the later start, like max_by_key.
+    let (count, i) = thread::scope(|scope| {
+        let workers: Vec<_> = (0..num_threads).map(|_| scope.spawn(|| {
+            let mut best = (0, 0);
+            loop {
+                let i = next_start.fetch_add(1, Ordering::Relaxed);
+                if i >= starts.len() {
+                    return best;
+                }
+                let (direction, point) = starts[i];
+                best = cmp::max(best, (tracer.count_energized(direction, point), i));
+            }
+        })).collect();
+
+        return workers.into_iter().map(|worker| worker.join().unwrap()).max().unwrap();
+    });
+
+    let (direction, point) = starts[i];
+    return (count, direction, point);
 }
 
-fn part1() {
+enum Output {
+    Count,
+    Energized,
+    Beams,
+    Ppm(String),
+    Pgm(String),
+}
+
+fn parse_output(args: &[String]) -> Output {
+    match (args.get(1).map(|a| a.as_str()), args.get(2)) {
+        (None, _) => Output::Count,
+        (Some("energized"), _) => Output::Energized,
+        (Some("beams"), _) => Output::Beams,
+        (Some("ppm"), Some(path)) => Output::Ppm(path.clone()),
+        (Some("pgm"), Some(path)) => Output::Pgm(path.clone()),
+        _ => panic!("Usage: day16 [energized|beams|ppm <file>|pgm <file>]")
+    }
+}
+
+fn show(output: &Output, grid: &Grid<u8>, energy: &Grid<u8>) {
+    match output {
+        Output::Count => {},
+        Output::Energized => println!("{}", energy_grid_to_string(energy)),
+        Output::Beams => println!("{}", beams_to_string(grid, energy)),
+        Output::Ppm(path) => fs::write(path, ppm_image(grid, energy)).unwrap(),
+        Output::Pgm(path) => fs::write(path, pgm_image(grid, energy)).unwrap(),
+    }
+}
+
+fn part2(output: &Output) {
     let content = fs::read_to_string("./src/input16.txt").unwrap();
     let grid: Grid<_> = content.lines().map(|l| l.bytes().collect()).collect();
 
-    println!("{}", fire_laser_and_count(&grid, (1, 0), (0, 0)));
+    let num_threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let (max_energized, direction, (x, y)) = best_edge_start(&grid, &ELEMENTS, num_threads);
+    println!("Best start: entering ({}, {}) heading {}", x, y, direction_name(direction));
+    show(output, &grid, &fire_laser_from(&grid, &ELEMENTS, direction, (x, y)));
+
+    println!("{max_energized}");
 }
 
-use std::thread;
+fn part1(output: &Output) {
+    let content = fs::read_to_string("./src/input16.txt").unwrap();
+    let grid: Grid<_> = content.lines().map(|l| l.bytes().collect()).collect();
+
+    let energy = fire_laser_from(&grid, &ELEMENTS, (1, 0), (0, 0));
+    show(output, &grid, &energy);
 
-const STACK_SIZE: usize = 4 * 1024 * 1024;
+    println!("{}", count_energized(&energy));
+}
 
+// Usage: day16 [energized|beams|ppm <file>|pgm <file>]
+// Draws the beams for the best start as well as printing the answer.
 fn main() {
-    // Spawn thread with explicit stack size (https://www.reddit.com/r/rust/comments/872fc4/how_to_increase_the_stack_size/)
-    let child = thread::Builder::new()
-        .stack_size(STACK_SIZE)
-        .spawn(part2)
-        .unwrap();
-
-    // Wait for thread to join
-    child.join().unwrap();
-}
\ No newline at end of file
+    let args: Vec<String> = env::args().collect();
+    part2(&parse_output(&args));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = r".|...\....
+|.-.\.....
+.....|-...
+........|.
+..........
+.........\
+..../.\\..
+.-.-/..|..
+.|....-|.\
+..//.|....";
+
+    fn example_grid() -> Grid<u8> {
+        return EXAMPLE.lines().map(|l| l.bytes().collect()).collect();
+    }
+
+    fn fire_laser_and_count(grid: &Grid<u8>, elements: &Elements, start_direction: Direction, start_point: (usize, usize)) -> usize {
+        return count_energized(&fire_laser_from(grid, elements, start_direction, start_point));
+    }
+
+    #[test]
+    fn test_fire_laser_and_count() {
+        assert_eq!(fire_laser_and_count(&example_grid(), &ELEMENTS, (1, 0), (0, 0)), 46);
+    }
+
+    #[test]
+    fn test_memoized_tracer() {
+        let grid = example_grid();
+        let tracer = MemoizedTracer::new(&grid, &ELEMENTS);
+
+        for (direction, point) in edge_starts(10, 10) {
+            assert_eq!(tracer.count_energized(direction, point), fire_laser_and_count(&grid, &ELEMENTS, direction, point));
+        }
+    }
+
+    #[test]
+    fn test_best_edge_start() {
+        let grid = example_grid();
+        for num_threads in 1..=4 {
+            assert_eq!(best_edge_start(&grid, &ELEMENTS, num_threads), (51, (0, 1), (3, 0)));
+        }
+    }
+
+    #[test]
+    fn test_beams_to_string() {
+        let grid = example_grid();
+        let energy = fire_laser_from(&grid, &ELEMENTS, (1, 0), (0, 0));
+
+        assert_eq!(beams_to_string(&grid, &energy), r">|<<<\....
+|v-.\^....
+.v...|->>>
+.v...v^.|.
+.v...v^...
+.v...v^..\
+.v../2\\..
+<->-/vv|..
+.|<<<2-|.\
+.v//.|.v..");
+    }
+
+    #[test]
+    fn test_pgm_image() {
+        let grid: Grid<u8> = vec![b"./".to_vec()];
+        let energy = fire_laser_from(&grid, &ELEMENTS, (1, 0), (0, 0));
+
+        let image = pgm_image(&grid, &energy);
+        let header = format!("P5\n{} {}\n255\n", 2 * PIXELS_PER_TILE, PIXELS_PER_TILE);
+        assert!(image.starts_with(header.as_bytes()));
+        assert_eq!(image.len(), header.len() + 2 * PIXELS_PER_TILE * PIXELS_PER_TILE);
+        assert_eq!(image[header.len()], 50);
+        assert_eq!(image[header.len() + PIXELS_PER_TILE], 90);
+    }
+
+    #[test]
+    fn test_elements() {
+        // From the top left: the + sends beams everywhere, the \ sends one down into the J, which turns it left into
+        // the absorber. The other goes down through the | and F and off the / into the F, which turns it down again.
+        let grid: Grid<u8> = ".+-\\\n.|#J\n.F..\nF/..\n....".lines().map(|l| l.bytes().collect()).collect();
+
+        assert_eq!(fire_laser_and_count(&grid, &ELEMENTS, (1, 0), (0, 0)), 11);
+        assert_eq!(MemoizedTracer::new(&grid, &ELEMENTS).count_energized((1, 0), (0, 0)), 11);
+    }
+
+    #[test]
+    fn test_custom_element() {
+        let mut elements = ELEMENTS.clone();
+        elements.insert(b'o', absorber);
+
+        let grid: Grid<u8> = vec!["..o..".bytes().collect()];
+        assert_eq!(fire_laser_and_count(&grid, &elements, (1, 0), (0, 0)), 3);
+    }
+
+    #[test]
+    fn test_strongly_connected_components() {
+        // 0 -> 1 -> 2 -> 0, 2 -> 3
+        let components = strongly_connected_components(&[vec![1], vec![2], vec![0, 3], vec![]]);
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0], vec![3]);
+
+        let mut cycle = components[1].clone();
+        cycle.sort();
+        assert_eq!(cycle, vec![0, 1, 2]);
+    }
+}
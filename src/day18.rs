@@ -1,70 +1,434 @@
+use std::env;
+use std::fmt;
 use std::fs;
 
-type Direction = (i64, i64);
+mod polygon;
 
-struct Instruction {
-    direction: Direction,
-    distance: i64,
+use polygon::{Direction, Instruction, Point, Polygon};
+
+// Each line of the dig plan read both ways: part 1 takes the direction and distance at face value,
+// part 2 decodes them from the hex code. The hex code is also the colour to paint the trench.
+#[derive(Debug, PartialEq)]
+struct DigStep {
+    line: usize,
+    literal: Instruction,
+    decoded: Instruction,
+    color: [u8; 3],
 }
 
-fn match_direction(direction: &str) -> Direction {
-    return match direction {
-        "U" => (0, -1),
-        "D" => (0, 1),
-        "L" => (-1, 0),
-        "R" => (1, 0),
-        _ => panic!()
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Encoding {
+    Literal,
+    Hex,
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Encoding::Literal => write!(f, "literal"),
+            Encoding::Hex => write!(f, "hex"),
+        }
     }
 }
 
-fn parse_instruction(line: &str) -> Instruction {
-    let mut parts = line.split(' ');
+#[derive(Debug, PartialEq)]
+enum DigPlanError {
+    Empty,
+    BadLine { line: usize },
+    BadDirection { line: usize, direction: String },
+    BadDistance { line: usize, distance: String },
+    BadHex { line: usize, hex: String },
+    NotClosed { encoding: Encoding, end: Point },
+    SelfIntersecting { encoding: Encoding, line: usize, other_line: usize },
+}
 
-    return Instruction {
-        direction: match_direction(parts.next().unwrap()),
-        distance: parts.next().unwrap().parse().unwrap(),
+impl fmt::Display for DigPlanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DigPlanError::Empty => write!(f, "there are no steps, so there's no trench"),
+            DigPlanError::BadLine { line } => write!(f, "line {}: expected DIRECTION DISTANCE (#rrggbb)", line),
+            DigPlanError::BadDirection { line, direction } => write!(f, "line {}: expected U, D, L or R, got '{}'", line, direction),
+            DigPlanError::BadDistance { line, distance } => write!(f, "line {}: expected a positive distance, got '{}'", line, distance),
+            DigPlanError::BadHex { line, hex } => write!(f, "line {}: expected (#) with five hex digits of distance and a direction from 0 to 3, got '{}'", line, hex),
+            DigPlanError::NotClosed { encoding, end } => write!(f, "the {} plan ends at {:?} instead of back at the start", encoding, end),
+            DigPlanError::SelfIntersecting { encoding, line, other_line } => write!(f, "line {}: the {} trench runs into the trench from line {}", line, encoding, other_line),
+        }
     }
 }
 
-fn parse_instruction_part2(line: &str) -> Instruction {
-    let raw_hex = line.split(' ').nth(2).unwrap();
-    let hex = &raw_hex[2..raw_hex.len()-1];
+fn parse_direction(line_number: usize, direction: &str) -> Result<Direction, DigPlanError> {
+    return match direction {
+        "U" => Ok((0, -1)),
+        "D" => Ok((0, 1)),
+        "L" => Ok((-1, 0)),
+        "R" => Ok((1, 0)),
+        _ => Err(DigPlanError::BadDirection { line: line_number, direction: direction.to_owned() })
+    };
+}
+
+// "(#70c710)": the first five digits are the distance and the last is the direction.
+// Returns the instruction and the code read as a colour.
+fn parse_hex(line_number: usize, raw_hex: &str) -> Result<(Instruction, [u8; 3]), DigPlanError> {
+    let bad_hex = || DigPlanError::BadHex { line: line_number, hex: raw_hex.to_owned() };
+
+    let hex = raw_hex.strip_prefix("(#").and_then(|hex| hex.strip_suffix(")")).ok_or_else(bad_hex)?;
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(bad_hex());
+    }
 
-    let dist = i64::from_str_radix(&hex[..5], 16).unwrap();
-    let dir = match &hex[5..] {
+    let distance = i64::from_str_radix(&hex[..5], 16).unwrap();
+    let direction = match &hex[5..] {
         "0" => (1, 0),
         "1" => (0, 1),
         "2" => (-1, 0),
         "3" => (0, -1),
-        _ => panic!()
+        _ => return Err(bad_hex())
     };
+    if distance == 0 {
+        return Err(bad_hex());
+    }
 
-    return Instruction {
-        direction: dir,
-        distance: dist
-    };
+    let color = [0, 2, 4].map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap());
+
+    return Ok((Instruction { direction: direction, distance: distance }, color));
+}
+
+fn parse_step(line_number: usize, line: &str) -> Result<DigStep, DigPlanError> {
+    let parts: Vec<&str> = line.split(' ').collect();
+    if parts.len() != 3 {
+        return Err(DigPlanError::BadLine { line: line_number });
+    }
+
+    let direction = parse_direction(line_number, parts[0])?;
+    let distance = parts[1].parse().ok().filter(|distance| *distance > 0)
+        .ok_or_else(|| DigPlanError::BadDistance { line: line_number, distance: parts[1].to_owned() })?;
+
+    let (decoded, color) = parse_hex(line_number, parts[2])?;
+
+    return Ok(DigStep {
+        line: line_number,
+        literal: Instruction { direction: direction, distance: distance },
+        decoded: decoded,
+        color: color,
+    });
+}
+
+// The smallest box containing a trench segment, as (min x, min y, max x, max y).
+// Segments are horizontal or vertical, so two of them cross exactly when their boxes overlap.
+fn segment_box(start: Point, instruction: &Instruction) -> (i64, i64, i64, i64) {
+    let (x, y) = start;
+    let (dx, dy) = instruction.direction;
+    let (end_x, end_y) = (x + dx * instruction.distance, y + dy * instruction.distance);
+    return (x.min(end_x), y.min(end_y), x.max(end_x), y.max(end_y));
+}
+
+fn boxes_overlap(a: (i64, i64, i64, i64), b: (i64, i64, i64, i64)) -> bool {
+    return a.0 <= b.2 && b.0 <= a.2 && a.1 <= b.3 && b.1 <= a.3;
+}
+
+// The trench has to get back to where it started, and only touch itself where one segment leads into the next.
+fn check_loop(encoding: Encoding, steps: &[DigStep]) -> Result<(), DigPlanError> {
+    let instruction = |step: &DigStep| if encoding == Encoding::Literal { step.literal } else { step.decoded };
+
+    let mut boxes = vec![];
+    let (mut x, mut y) = (0, 0);
+    for step in steps {
+        let instruction = instruction(step);
+        boxes.push(segment_box((x, y), &instruction));
+        x += instruction.direction.0 * instruction.distance;
+        y += instruction.direction.1 * instruction.distance;
+    }
+
+    if (x, y) != (0, 0) {
+        return Err(DigPlanError::NotClosed { encoding: encoding, end: (x, y) });
+    }
+
+    let n = steps.len();
+    for i in 0..n {
+        for j in 0..i {
+            let adjacent = j + 1 == i || (j == 0 && i == n - 1);
+            let crosses = if adjacent {
+                // Neighbors always share a corner, so they only run into each other by doubling back.
+                let (dx_i, dy_i) = instruction(&steps[i]).direction;
+                instruction(&steps[j]).direction == (-dx_i, -dy_i)
+            } else {
+                boxes_overlap(boxes[i], boxes[j])
+            };
+
+            if crosses {
+                return Err(DigPlanError::SelfIntersecting { encoding: encoding, line: steps[i].line, other_line: steps[j].line });
+            }
+        }
+    }
+
+    return Ok(());
+}
+
+// Lines like "R 6 (#70c710)". Both readings of the plan have to make a closed loop that doesn't cross itself.
+fn parse_dig_plan(content: &str) -> Result<Vec<DigStep>, DigPlanError> {
+    let steps = content.lines().enumerate()
+        .map(|(i, line)| parse_step(i + 1, line))
+        .collect::<Result<Vec<DigStep>, DigPlanError>>()?;
+    if steps.is_empty() {
+        return Err(DigPlanError::Empty);
+    }
+
+    check_loop(Encoding::Literal, &steps)?;
+    check_loop(Encoding::Hex, &steps)?;
+
+    return Ok(steps);
+}
+
+// (min x, min y, max x, max y) over every corner of the trench.
+fn bounds(vertices: &[Point]) -> (i64, i64, i64, i64) {
+    let min_x = vertices.iter().map(|(x, _)| *x).min().unwrap();
+    let min_y = vertices.iter().map(|(_, y)| *y).min().unwrap();
+    let max_x = vertices.iter().map(|(x, _)| *x).max().unwrap();
+    let max_y = vertices.iter().map(|(_, y)| *y).max().unwrap();
+    return (min_x, min_y, max_x, max_y);
+}
+
+// Anything bigger than this is better off as an image.
+const MAX_ASCII_SIZE: i64 = 1000;
+
+// The trench and the lagoon inside it as #s. None if the plan is too big to print, like any part 2 plan.
+fn lagoon_to_string(instructions: &[Instruction]) -> Option<String> {
+    let vertices = Polygon::from_instructions(instructions).vertices;
+    let (min_x, min_y, max_x, max_y) = bounds(&vertices);
+    if max_x - min_x >= MAX_ASCII_SIZE || max_y - min_y >= MAX_ASCII_SIZE {
+        return None;
+    }
+
+    // Leave a ring of ground around the outside so it's all connected.
+    let width = (max_x - min_x + 3) as usize;
+    let height = (max_y - min_y + 3) as usize;
+    let mut dug = vec![vec![true; width]; height];
+    let mut trench = vec![vec![false; width]; height];
+
+    let (mut x, mut y) = ((1 - min_x) as usize, (1 - min_y) as usize);
+    for instruction in instructions {
+        for _ in 0..instruction.distance {
+            trench[y][x] = true;
+            x = x.checked_add_signed(instruction.direction.0 as isize).unwrap();
+            y = y.checked_add_signed(instruction.direction.1 as isize).unwrap();
+        }
+    }
+
+    // Everything the outside can reach without crossing the trench isn't dug.
+    let mut stack: Vec<(usize, usize)> = vec![(0, 0)];
+    dug[0][0] = false;
+    while let Some((x, y)) = stack.pop() {
+        for (dx, dy) in [(0, 1), (0, -1), (1, 0), (-1, 0)] {
+            let (Some(new_x), Some(new_y)) = (x.checked_add_signed(dx), y.checked_add_signed(dy)) else { continue };
+            if new_x < width && new_y < height && dug[new_y][new_x] && !trench[new_y][new_x] {
+                dug[new_y][new_x] = false;
+                stack.push((new_x, new_y));
+            }
+        }
+    }
+
+    let rows: Vec<String> = dug[1..height - 1].iter()
+        .map(|row| row[1..width - 1].iter().map(|d| if *d { '#' } else { '.' }).collect())
+        .collect();
+    return Some(rows.join("\n"));
 }
 
-fn compute_area(instructions: Vec<Instruction>) -> i64 {
-    let mut total_len = 0;
-    let mut curr_y = 0;
-    let mut area = 0;
+// The longer side of the image, however big the plan is.
+const IMAGE_SIZE: usize = 800;
+
+const GROUND_COLOR: [u8; 3] = [40, 30, 20];
+const LAGOON_COLOR: [u8; 3] = [110, 110, 110];
+
+// A binary PPM (P6) scaled to fit in IMAGE_SIZE pixels, with the lagoon filled in and each segment of trench
+// painted its own colour.
+fn lagoon_ppm(instructions: &[Instruction], colors: &[[u8; 3]]) -> Vec<u8> {
+    let vertices = Polygon::from_instructions(instructions).vertices;
+    let (min_x, min_y, max_x, max_y) = bounds(&vertices);
+
+    // Each square of the plan is `scale` pixels across, which is usually much less than one for part 2.
+    let scale = IMAGE_SIZE as f64 / (max_x - min_x + 1).max(max_y - min_y + 1) as f64;
+    let width = (((max_x - min_x + 1) as f64 * scale).ceil() as usize).max(1);
+    let height = (((max_y - min_y + 1) as f64 * scale).ceil() as usize).max(1);
+    let mut pixels = vec![vec![GROUND_COLOR; width]; height];
 
-    for instr in instructions {
-        let (dx, dy) = instr.direction;
-        curr_y += dy * instr.distance;
-        area += curr_y * dx * instr.distance;
-        total_len += instr.distance;
+    // Fill in the lagoon a row at a time: a pixel is inside if there are an odd number of vertical trench
+    // segments to its left at that height.
+    let n = vertices.len();
+    for (py, row) in pixels.iter_mut().enumerate() {
+        let y = min_y as f64 + (py as f64 + 0.5) / scale - 0.5;
+
+        let mut crossings: Vec<f64> = (0..n).filter_map(|i| {
+            let ((x1, y1), (x2, y2)) = (vertices[i], vertices[(i + 1) % n]);
+            let crosses = x1 == x2 && (y1.min(y2) as f64) <= y && y < (y1.max(y2) as f64);
+            return if crosses { Some(x1 as f64) } else { None };
+        }).collect();
+        crossings.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        for pair in crossings.chunks(2) {
+            if let [left, right] = pair {
+                let start = (((left - min_x as f64 + 0.5) * scale).floor() as usize).min(width);
+                let end = (((right - min_x as f64 + 0.5) * scale).ceil() as usize).min(width);
+                row[start..end].fill(LAGOON_COLOR);
+            }
+        }
     }
 
-    let adjusted_area = total_len / 2 + area.abs() + 1;
+    // Then paint every segment over the top, at least a pixel thick.
+    let to_pixels = |low: i64, high: i64, min: i64, size: usize| {
+        let start = (((low - min) as f64 * scale).floor() as usize).min(size - 1);
+        let end = (((high - min + 1) as f64 * scale).ceil() as usize).clamp(start + 1, size);
+        return start..end;
+    };
+    for (i, color) in colors.iter().enumerate() {
+        let ((x1, y1), (x2, y2)) = (vertices[i], vertices[(i + 1) % n]);
+        for py in to_pixels(y1.min(y2), y1.max(y2), min_y, height) {
+            pixels[py][to_pixels(x1.min(x2), x1.max(x2), min_x, width)].fill(*color);
+        }
+    }
 
-    return adjusted_area;
+    let mut image = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    for row in pixels {
+        for pixel in row {
+            image.extend_from_slice(&pixel);
+        }
+    }
+    return image;
 }
 
+// Usage: day18 [ascii | ppm <file> [hex]]
+// ascii prints the part 1 lagoon. ppm draws it as an image, or the part 2 lagoon with hex.
 fn main() {
     let contents = fs::read_to_string("./src/input18.txt").unwrap();
-    // let instructions: Vec<Instruction> = contents.lines().map(parse_instruction).collect();
-    let instructions: Vec<Instruction> = contents.lines().map(parse_instruction_part2).collect();
-    println!("{}", compute_area(instructions));
-}
\ No newline at end of file
+
+    let plan = match parse_dig_plan(&contents) {
+        Ok(plan) => plan,
+        Err(err) => {
+            println!("Invalid dig plan: {}", err);
+            return;
+        }
+    };
+
+    let literal: Vec<Instruction> = plan.iter().map(|step| step.literal).collect();
+    let decoded: Vec<Instruction> = plan.iter().map(|step| step.decoded).collect();
+    println!("{}", Polygon::from_instructions(&literal).area_with_boundary());
+    println!("{}", Polygon::from_instructions(&decoded).area_with_boundary());
+
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(|a| a.as_str()) {
+        Some("ascii") => match lagoon_to_string(&literal) {
+            Some(lagoon) => println!("{}", lagoon),
+            None => println!("Too big to print, try ppm"),
+        },
+        Some("ppm") => {
+            let instructions = if args.get(3).map(|a| a.as_str()) == Some("hex") { &decoded } else { &literal };
+            let colors: Vec<[u8; 3]> = plan.iter().map(|step| step.color).collect();
+            fs::write(&args[2], lagoon_ppm(instructions, &colors)).unwrap();
+        },
+        _ => {},
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "R 6 (#70c710)
+D 5 (#0dc571)
+L 2 (#5713f0)
+D 2 (#d2c081)
+R 2 (#59c680)
+D 2 (#411b91)
+L 5 (#8ceee2)
+U 2 (#caa173)
+L 1 (#1b58a2)
+U 2 (#caa171)
+R 2 (#7807d2)
+U 3 (#a77fa3)
+L 2 (#015232)
+U 2 (#7a21e3)";
+
+    #[test]
+    fn test_parse_dig_plan() {
+        let plan = parse_dig_plan(EXAMPLE).unwrap();
+        assert_eq!(plan[0], DigStep {
+            line: 1,
+            literal: Instruction { direction: (1, 0), distance: 6 },
+            decoded: Instruction { direction: (1, 0), distance: 461937 },
+            color: [0x70, 0xc7, 0x10],
+        });
+
+        let literal: Vec<Instruction> = plan.iter().map(|step| step.literal).collect();
+        let decoded: Vec<Instruction> = plan.iter().map(|step| step.decoded).collect();
+        assert_eq!(Polygon::from_instructions(&literal).area_with_boundary(), 62);
+        assert_eq!(Polygon::from_instructions(&decoded).area_with_boundary(), 952408144115);
+    }
+
+    #[test]
+    fn test_bad_lines() {
+        assert_eq!(parse_dig_plan("R 6 (#70c710)\nX 5 (#0dc571)"), Err(DigPlanError::BadDirection { line: 2, direction: "X".to_owned() }));
+        assert_eq!(parse_dig_plan("R -6 (#70c710)"), Err(DigPlanError::BadDistance { line: 1, distance: "-6".to_owned() }));
+        assert_eq!(parse_dig_plan("R 6 (#70c714)"), Err(DigPlanError::BadHex { line: 1, hex: "(#70c714)".to_owned() }));
+        assert_eq!(parse_dig_plan("R 6 #70c710"), Err(DigPlanError::BadHex { line: 1, hex: "#70c710".to_owned() }));
+        assert_eq!(parse_dig_plan("R 6"), Err(DigPlanError::BadLine { line: 1 }));
+    }
+
+    #[test]
+    fn test_bad_loops() {
+        assert_eq!(parse_dig_plan(""), Err(DigPlanError::Empty));
+
+        // Every hex code is "right 1", so the hex plan is never the one at fault.
+        assert_eq!(parse_dig_plan("R 2 (#000010)\nD 2 (#000010)"), Err(DigPlanError::NotClosed { encoding: Encoding::Literal, end: (2, 2) }));
+
+        // A figure of eight: the L 4 cuts across the D 2.
+        let figure_eight = "R 2 (#000010)\nD 2 (#000010)\nR 2 (#000010)\nU 1 (#000010)\nL 4 (#000010)\nU 1 (#000010)";
+        assert_eq!(parse_dig_plan(figure_eight), Err(DigPlanError::SelfIntersecting { encoding: Encoding::Literal, line: 5, other_line: 2 }));
+
+        let doubling_back = "R 2 (#000010)\nL 1 (#000010)\nD 1 (#000010)\nL 1 (#000010)\nU 1 (#000010)";
+        assert_eq!(parse_dig_plan(doubling_back), Err(DigPlanError::SelfIntersecting { encoding: Encoding::Literal, line: 2, other_line: 1 }));
+
+        // The literal plan is fine, but the hex one goes right forever.
+        assert_eq!(parse_dig_plan("R 1 (#000010)\nD 1 (#000010)\nL 1 (#000010)\nU 1 (#000010)"),
+            Err(DigPlanError::NotClosed { encoding: Encoding::Hex, end: (4, 0) }));
+    }
+
+    #[test]
+    fn test_lagoon_to_string() {
+        let plan = parse_dig_plan(EXAMPLE).unwrap();
+        let literal: Vec<Instruction> = plan.iter().map(|step| step.literal).collect();
+        let decoded: Vec<Instruction> = plan.iter().map(|step| step.decoded).collect();
+
+        assert_eq!(lagoon_to_string(&literal).unwrap(), "#######
+#######
+#######
+..#####
+..#####
+#######
+#####..
+#######
+.######
+.######");
+        assert_eq!(lagoon_to_string(&decoded), None);
+    }
+
+    #[test]
+    fn test_lagoon_ppm() {
+        let plan = parse_dig_plan(EXAMPLE).unwrap();
+        let literal: Vec<Instruction> = plan.iter().map(|step| step.literal).collect();
+        let colors: Vec<[u8; 3]> = plan.iter().map(|step| step.color).collect();
+
+        // The example is 7 by 10, so each square is 80 pixels across.
+        let image = lagoon_ppm(&literal, &colors);
+        let header = "P6\n560 800\n255\n";
+        assert!(image.starts_with(header.as_bytes()));
+        assert_eq!(image.len(), header.len() + 560 * 800 * 3);
+
+        let pixel = |x: usize, y: usize| {
+            let i = header.len() + (y * 560 + x) * 3;
+            return [image[i], image[i + 1], image[i + 2]];
+        };
+        // The middle of the first segment, then inside the lagoon, then outside it.
+        assert_eq!(pixel(3 * 80 + 40, 40), [0x70, 0xc7, 0x10]);
+        assert_eq!(pixel(3 * 80 + 40, 3 * 80 + 40), LAGOON_COLOR);
+        assert_eq!(pixel(40, 3 * 80 + 40), GROUND_COLOR);
+    }
+}
@@ -1,6 +1,11 @@
 use std::fs;
 use std::collections::{HashMap,VecDeque};
-use num::integer::lcm;
+
+mod named_graph;
+mod number_theory;
+
+use named_graph::NamedGraph;
+use number_theory::{crt_all, first_at_least};
 
 struct FlipFlopState {
     state: bool
@@ -55,16 +60,7 @@ fn part1() {
         .map(parse_module)
         .map(|module| (module.name, module)).collect();
 
-    let mut module_to_inputs: HashMap<&str, Vec<&str>> = HashMap::new();
-    for module in modules.values() {
-        for output in module.outputs.iter() {
-            if let Some(inputs) = module_to_inputs.get_mut(output) {
-                inputs.push(module.name);
-            } else {
-                module_to_inputs.insert(output, vec![module.name]);
-            }
-        }
-    }
+    let wiring = NamedGraph::from_arrow_lines(&contents, &['%', '&']);
 
     let mut low_count = 0;
     let mut high_count = 0;
@@ -101,7 +97,8 @@ fn part1() {
                     }
                     ModuleType::Conjunction(state) => {
                         state.input_states.insert(src_name, value);
-                        let all_initialized = module_to_inputs.get(module.name).unwrap().len() == state.input_states.len();
+                        let num_inputs = wiring.incoming(wiring.idx(module.name).unwrap()).len();
+                        let all_initialized = num_inputs == state.input_states.len();
                         Some(!(all_initialized && state.input_states.values().all(|v| *v)))
                     }
                 };
@@ -119,8 +116,11 @@ fn part1() {
 }
 
 fn part2() {
-    // Periods for vz, bq, qh, and lt
-    println!("{}", vec![4093_i64, 3889, 3821, 3739].into_iter().reduce(lcm).unwrap());
+    // Periods for vz, bq, qh, and lt. Each sends a high pulse on the presses that are multiples of its period.
+    let periods: [i128; 4] = [4093, 3889, 3821, 3739];
+    let congruences: Vec<(i128, i128)> = periods.iter().map(|period| (0, *period)).collect();
+    let (residue, modulus) = crt_all(&congruences).unwrap();
+    println!("{}", first_at_least(&[residue], modulus, 1).unwrap());
 }
 
 fn main() {
@@ -1,4 +1,5 @@
 use std::fs;
+use std::collections::{HashMap, VecDeque};
 
 type Grid<T> = Vec<Vec<T>>;
 
@@ -28,44 +29,32 @@ fn neighbors(point: (usize, usize), width: usize, height: usize) -> impl Iterato
     return DIRECTIONS.iter().filter_map(move |d| step_in_direction(point, *d, width, height));
 }
 
-fn step(grid: &Grid<u8>, locations: &Grid<bool>) -> Grid<bool> {
+// Shortest number of steps from the start to every garden plot, or None for rocks and plots that can't be reached.
+fn bfs_distances(grid: &Grid<u8>, start: (usize, usize)) -> Grid<Option<usize>> {
     let height = grid.len();
     let width = grid[0].len();
+    let (start_x, start_y) = start;
 
-    let mut new_locations = blank_grid(width, height, false);
+    let mut distances = blank_grid(width, height, None);
+    distances[start_y][start_x] = Some(0);
 
-    for y in 0..height {
-        for x in 0..width {
-            if locations[y][x] {
-                for (new_x, new_y) in neighbors((x, y), width, height) {
-                    if grid[new_y][new_x] != b'#' {
-                        new_locations[new_y][new_x] = true;
-                    }
-                }
+    let mut queue = VecDeque::from([start]);
+    while let Some((x, y)) = queue.pop_front() {
+        let d = distances[y][x].unwrap();
+        for (new_x, new_y) in neighbors((x, y), width, height) {
+            if grid[new_y][new_x] != b'#' && distances[new_y][new_x].is_none() {
+                distances[new_y][new_x] = Some(d + 1);
+                queue.push_back((new_x, new_y));
             }
         }
     }
 
-    return new_locations;
-}
-
-fn count_true(grid: &Grid<bool>) -> usize {
-    return grid.iter().map(|row| row.iter().filter(|l| **l).count()).sum::<usize>();
+    return distances;
 }
 
-fn count_reachable_after_steps(grid: &Grid<u8>, start: (usize, usize), steps: usize) -> usize {
-    let height = grid.len();
-    let width = grid[0].len();
-    let (start_x, start_y) = start;
-
-    let mut locations = blank_grid(width, height, false);
-    locations[start_y][start_x] = true;
-
-    for i in 0..steps {
-        locations = step(&grid, &locations);
-    }
-
-    return count_true(&locations);
+// Number of plots you can be standing on after exactly `steps`. One BFS answers this for any number of steps.
+fn reachable_count(distances: &Grid<Option<usize>>, steps: usize) -> usize {
+    return distances.iter().flatten().flatten().filter(|d| is_reachable_in(**d as u64, steps as u64)).count();
 }
 
 fn part1() {
@@ -77,106 +66,390 @@ fn part1() {
             |(y, row)| row.iter().enumerate().find_map(
                 |(x, c)| if *c == b'S' {Some((x, y))} else {None})).unwrap();
 
-    println!("{}", count_reachable_after_steps(&grid, (start_x, start_y), 64));
+    println!("{}", reachable_count(&bfs_distances(&grid, (start_x, start_y)), 64));
 }
 
-#[derive(Debug)]
-struct StartsAndSteps {
-    start: (usize, usize),
-    steps: usize,
-    count: usize,
+// Distances from the start on the infinitely tiled map, for the tiles within `radius` tiles of the starting one.
+// Distances near the edge of this window can come out too long, since the real shortest path might leave it.
+struct TiledDistances {
+    width: usize,
+    height: usize,
+    radius: usize,
+    distances: Vec<u32>,
 }
 
-fn compute_number_completed_grids(grid_size: usize, total_steps: usize) -> (usize, usize) {
-    let n = (total_steps + 1) / grid_size - 1;
+const UNREACHABLE: u32 = u32::MAX;
+
+impl TiledDistances {
+    fn new(grid: &Grid<u8>, start: (usize, usize), radius: usize) -> TiledDistances {
+        let height = grid.len();
+        let width = grid[0].len();
+        let total_width = (2 * radius + 1) * width;
+        let total_height = (2 * radius + 1) * height;
+
+        let mut distances = vec![UNREACHABLE; total_width * total_height];
+        let (start_x, start_y) = start;
+        let start = (radius * width + start_x, radius * height + start_y);
+        distances[start.1 * total_width + start.0] = 0;
+
+        let mut queue = VecDeque::from([start]);
+        while let Some((x, y)) = queue.pop_front() {
+            let d = distances[y * total_width + x];
+            for (new_x, new_y) in neighbors((x, y), total_width, total_height) {
+                let i = new_y * total_width + new_x;
+                if grid[new_y % height][new_x % width] != b'#' && distances[i] == UNREACHABLE {
+                    distances[i] = d + 1;
+                    queue.push_back((new_x, new_y));
+                }
+            }
+        }
 
-    let total_completed = 4 * (n * (n + 1) / 2) + 1;
+        return TiledDistances { width: width, height: height, radius: radius, distances: distances };
+    }
 
-    let red_n = (n - 1) / 2;
-    let black_n = n / 2;
+    // Distances for every cell of a tile, row by row. Tile (0, 0) holds the start.
+    fn tile(&self, tile: (isize, isize)) -> Vec<Option<u64>> {
+        let (tile_x, tile_y) = tile;
+        let total_width = (2 * self.radius + 1) * self.width;
+        let offset_x = (tile_x + self.radius as isize) as usize * self.width;
+        let offset_y = (tile_y + self.radius as isize) as usize * self.height;
+
+        return (0..self.height).flat_map(|y| (0..self.width).map(move |x| (x, y))).map(|(x, y)| {
+            let d = self.distances[(offset_y + y) * total_width + offset_x + x];
+            if d == UNREACHABLE { None } else { Some(d as u64) }
+        }).collect();
+    }
+}
 
-    let completed_black = 4 * (black_n * (black_n + 1)) + 1;
-    let completed_red = 4 * (red_n + 1) * (red_n + 1);
+// You can waste steps by walking back and forth, so a cell is reachable in exactly `steps` if it's close enough
+// and the parity matches.
+fn is_reachable_in(distance: u64, steps: u64) -> bool {
+    return distance <= steps && distance % 2 == steps % 2;
+}
 
-    assert_eq!(completed_black + completed_red, total_completed);
+// How many of first, first + step, first + 2 * step, ... are reachable in exactly `steps`.
+fn count_terms(first: u64, step: u64, steps: u64) -> u64 {
+    if first > steps {
+        return 0;
+    }
 
-    return (completed_black, completed_red);
+    let num_terms = (steps - first) / step + 1;
+    if step % 2 == 0 {
+        return if first % 2 == steps % 2 { num_terms } else { 0 };
+    }
+
+    // The parity alternates, so every other term counts, starting with the first or the second.
+    let skip = if first % 2 == steps % 2 { 0 } else { 1 };
+    return (num_terms + 1 - skip) / 2;
 }
 
-fn get_starts_and_steps(grid_size: usize, total_steps: usize) -> Vec<StartsAndSteps> {
-    let mut result: Vec<StartsAndSteps> = vec![];
+fn is_shifted(tile: &[Option<u64>], further_tile: &[Option<u64>], shift: u64) -> bool {
+    return tile.iter().zip(further_tile.iter()).all(|(d, further_d)| *further_d == d.map(|d| d + shift));
+}
 
-    let half_size = grid_size / 2;
+// How much further every cell of `further_tile` is than the same cell of `tile`, if it's the same for all of them.
+fn measure_shift(tile: &[Option<u64>], further_tile: &[Option<u64>]) -> Option<u64> {
+    let (d, further_d) = tile.iter().zip(further_tile.iter()).find_map(|(d, further_d)| d.zip(*further_d))?;
+    let shift = further_d.checked_sub(d).filter(|shift| *shift > 0)?;
+    return if is_shifted(tile, further_tile, shift) { Some(shift) } else { None };
+}
+
+fn tile_add(tile: (isize, isize), direction: (isize, isize), times: isize) -> (isize, isize) {
+    return (tile.0 + direction.0 * times, tile.1 + direction.1 * times);
+}
+
+// Far enough from the start, every tile's distances are just the previous tile's plus a fixed shift for each direction.
+// That's the tile width (or height) when there's a clear row (or column) to walk along, and more when paths have to
+// weave between rocks, so the shifts are measured rather than assumed. If they hold at the edge of our window,
+// everything beyond it can be counted without visiting it. Returns None if the distances haven't settled into that
+// pattern yet.
+//
+// Each direction has to have the same shift all along the boundary, and the quadrants have to use the shifts of the
+// directions either side of them. Otherwise, say, weaving diagonally is cheaper than weaving straight across, and
+// tiles near the corners settle into a shift that tiles far out along the axes don't keep.
+fn count_reachable_if_stable(distances: &TiledDistances, steps: u64) -> Option<u64> {
+    let b = distances.radius as isize - 3;
+
+    let mut count = 0;
+    let mut axis_shifts: HashMap<Direction, u64> = HashMap::new();
+
+    // Tiles inside the settled boundary.
+    for tile_y in -b..=b {
+        for tile_x in -b..=b {
+            count += distances.tile((tile_x, tile_y)).iter().flatten().filter(|d| is_reachable_in(**d, steps)).count() as u64;
+        }
+    }
 
-    println!("{}", half_size * half_size);
+    // Straight out from the boundary in each direction, one row or column of tiles at a time.
+    for direction in DIRECTIONS {
+        let (dx, dy) = direction;
+
+        for offset in -b..=b {
+            let base = (dx * b + dy.abs() * offset, dy * b + dx.abs() * offset);
+            let tile = distances.tile(base);
+
+            let shift = measure_shift(&tile, &distances.tile(tile_add(base, direction, 1)))?;
+            if !is_shifted(&tile, &distances.tile(tile_add(base, direction, 2)), 2 * shift)
+                || *axis_shifts.entry(direction).or_insert(shift) != shift {
+                return None;
+            }
+
+            count += tile.iter().flatten().map(|d| count_terms(d + shift, shift, steps)).sum::<u64>();
+        }
+    }
+
+    // The four diagonal quadrants. Tile (b + i, b + j) is tile (b, b) shifted by i * shift_x + j * shift_y.
+    for (sx, sy) in [(1, 1), (1, -1), (-1, 1), (-1, -1)] {
+        let base = (sx * b, sy * b);
+        let tile = distances.tile(base);
+        let shift_x = axis_shifts[&(sx, 0)];
+        let shift_y = axis_shifts[&(0, sy)];
+
+        for (i, j) in [(1, 1), (2, 1), (1, 2), (2, 2)] {
+            let further = (sx * (b + i), sy * (b + j));
+            if !is_shifted(&tile, &distances.tile(further), i as u64 * shift_x + j as u64 * shift_y) {
+                return None;
+            }
+        }
+
+        // Plenty of cells share a distance, so count each distance once.
+        let mut distance_counts: HashMap<u64, u64> = HashMap::new();
+        for d in tile.iter().flatten() {
+            *distance_counts.entry(*d).or_insert(0) += 1;
+        }
+
+        for (d, num_cells) in distance_counts {
+            let mut i = 1;
+            while d + i * shift_x + shift_y <= steps {
+                count += num_cells * count_terms(d + i * shift_x + shift_y, shift_y, steps);
+                i += 1;
+            }
+        }
+    }
 
-    let steps_to_complete_black = half_size * 2 + (total_steps % 2);
-    let steps_to_complete_red = half_size * 2 + ((total_steps + 1) % 2);
+    return Some(count);
+}
 
-    // Add entries for completed grids.
-    let (completed_black, completed_red) = compute_number_completed_grids(grid_size, total_steps);
-    result.push(StartsAndSteps {start: (half_size, half_size), steps: steps_to_complete_black, count: completed_black});
-    result.push(StartsAndSteps {start: (half_size, half_size), steps: steps_to_complete_red, count: completed_red});
+// The most cells reachable_counts will search, about 128MB of distances.
+const MAX_WINDOW_CELLS: usize = 1 << 25;
 
-    let steps_for_rhombus_corners = (total_steps - half_size - 1) % grid_size;
-    println!("steps_for_rhombus_corners={}", steps_for_rhombus_corners);
-    result.push(StartsAndSteps {start: (0, half_size), steps: steps_for_rhombus_corners, count: 1});
-    result.push(StartsAndSteps {start: (grid_size - 1, half_size), steps: steps_for_rhombus_corners, count: 1});
-    result.push(StartsAndSteps {start: (half_size, 0), steps: steps_for_rhombus_corners, count: 1});
-    result.push(StartsAndSteps {start: (half_size, grid_size - 1), steps: steps_for_rhombus_corners, count: 1});
+// The count for every number of steps from 0 to max_steps, from one search big enough to get them all right.
+// None if that search would need more than MAX_WINDOW_CELLS.
+fn reachable_counts(grid: &Grid<u8>, start: (usize, usize), max_steps: u64) -> Option<Vec<u64>> {
+    let tile_size = grid.len().min(grid[0].len()) as u64;
+    let radius = (max_steps / tile_size + 2) as usize;
+    if (2 * radius + 1).pow(2) * grid.len() * grid[0].len() > MAX_WINDOW_CELLS {
+        return None;
+    }
+    let distances = TiledDistances::new(grid, start, radius);
 
-    // A second set of rhomus corners may be further along but not completed.
-    let steps_for_old_rhombus_corners = steps_for_rhombus_corners + grid_size;
-    let steps_to_complete_rhombus_corners = 3 * half_size;
-    if steps_for_old_rhombus_corners < steps_to_complete_rhombus_corners {
-        result.push(StartsAndSteps {start: (0, half_size), steps: steps_for_old_rhombus_corners, count: 1});
-        result.push(StartsAndSteps {start: (grid_size - 1, half_size), steps: steps_for_old_rhombus_corners, count: 1});
-        result.push(StartsAndSteps {start: (half_size, 0), steps: steps_for_old_rhombus_corners, count: 1});
-        result.push(StartsAndSteps {start: (half_size, grid_size - 1), steps: steps_for_old_rhombus_corners, count: 1});
+    // Cells at each distance, then each count adds up every other distance below it.
+    let mut counts = vec![0; max_steps as usize + 1];
+    for d in distances.distances.iter() {
+        if (*d as u64) <= max_steps {
+            counts[*d as usize] += 1;
+        }
     }
+    for n in 2..counts.len() {
+        counts[n] += counts[n - 2];
+    }
+    return Some(counts);
+}
+
+// The shortest period for which every count in the second half lies on a quadratic with the counts one, two and
+// three periods before it. The period can be at most a sixteenth of the counts, so each one is checked over
+// several periods in a row.
+fn quadratic_period(counts: &[u64]) -> Option<usize> {
+    let max_steps = counts.len() - 1;
+    let c = |n: usize| counts[n] as i128;
+    return (1..=max_steps / 16).find(|p| {
+        (max_steps / 2 + 3 * p..=max_steps).all(|n| c(n) - 3 * c(n - p) + 3 * c(n - 2 * p) - c(n - 3 * p) == 0)
+    });
+}
+
+// Once the reachable area has settled into a repeating shape, the count grows quadratically with each period.
+// The period depends on how paths weave between the rocks, so it's found by counting exactly up to some number of
+// steps, and then three samples are enough to extrapolate. If no period has held for long enough, count twice as far.
+// None if the period is too long to find before the search gets too big.
+fn count_reachable_quadratic(grid: &Grid<u8>, start: (usize, usize), steps: u64) -> Option<u64> {
+    let mut max_steps = 32 * grid.len().max(grid[0].len()) as u64;
+    loop {
+        let counts = reachable_counts(grid, start, max_steps.min(steps))?;
+        if steps <= max_steps {
+            return Some(counts[steps as usize]);
+        }
+
+        if let Some(period) = quadratic_period(&counts) {
+            let period = period as u64;
+            // The last sample is the last count with the same number of steps mod the period.
+            let last = max_steps - (max_steps % period + period - steps % period) % period;
+            let sample = |k: u64| counts[(last - k * period) as usize] as i128;
+            let n = ((steps - last) / period) as i128;
+
+            let first_difference = sample(0) - sample(1);
+            let second_difference = sample(0) - 2 * sample(1) + sample(2);
+            return Some((sample(0) + n * first_difference + n * (n + 1) / 2 * second_difference) as u64);
+        }
 
-    let rhombus_sides_cnt = (total_steps - 1) / grid_size;
-    let steps_for_rhombus_sides = (total_steps - grid_size - 1) % grid_size;
-    result.push(StartsAndSteps {start: (0, 0), steps: steps_for_rhombus_sides, count: rhombus_sides_cnt});
-    result.push(StartsAndSteps {start: (0, grid_size - 1), steps: steps_for_rhombus_sides, count: rhombus_sides_cnt});
-    result.push(StartsAndSteps {start: (grid_size - 1, 0), steps: steps_for_rhombus_sides, count: rhombus_sides_cnt});
-    result.push(StartsAndSteps {start: (grid_size - 1, grid_size - 1), steps: steps_for_rhombus_sides, count: rhombus_sides_cnt});
+        max_steps *= 2;
+    }
+}
 
-    let old_rhombus_sides_cnt = rhombus_sides_cnt - 1;
-    let steps_for_old_rhombus_sides = steps_for_rhombus_sides + grid_size;
-    let steps_to_complete_rhombus_sides = 4 * half_size;
-    if steps_for_old_rhombus_sides < steps_to_complete_rhombus_sides {
-        result.push(StartsAndSteps {start: (0, 0), steps: steps_for_old_rhombus_sides, count: old_rhombus_sides_cnt});
-        result.push(StartsAndSteps {start: (0, grid_size - 1), steps: steps_for_old_rhombus_sides, count: old_rhombus_sides_cnt});
-        result.push(StartsAndSteps {start: (grid_size - 1, 0), steps: steps_for_old_rhombus_sides, count: old_rhombus_sides_cnt});
-        result.push(StartsAndSteps {start: (grid_size - 1, grid_size - 1), steps: steps_for_old_rhombus_sides, count: old_rhombus_sides_cnt});
+const MIN_TILE_RADIUS: usize = 3;
+const MAX_TILE_RADIUS: usize = 11;
+
+// Number of garden plots reachable in exactly `steps` on the infinitely tiled map. Any start position and grid shape
+// works, not just a square grid with the start in the middle, as long as the tiles settle into fixed shifts or the
+// count repeats quickly enough to find. Grids with no clear rows or columns often do neither, and give None for
+// large numbers of steps.
+fn count_reachable_infinite(grid: &Grid<u8>, start: (usize, usize), steps: u64) -> Option<u64> {
+    for radius in (MIN_TILE_RADIUS..=MAX_TILE_RADIUS).step_by(2) {
+        let distances = TiledDistances::new(grid, start, radius);
+        if let Some(count) = count_reachable_if_stable(&distances, steps) {
+            return Some(count);
+        }
     }
 
-    return result;
+    return count_reachable_quadratic(grid, start, steps);
 }
 
 fn part2() {
     let content = fs::read_to_string("./src/input21.txt").unwrap();
     let grid: Grid<_> = content.lines().map(|l| l.bytes().collect()).collect();
 
-    let size = grid.len();
-    assert_eq!(size, grid[0].len(), "Grid must be square");
-    
     let (start_x, start_y) = 
     grid.iter().enumerate().find_map(
         |(y, row)| row.iter().enumerate().find_map(
             |(x, c)| if *c == b'S' {Some((x, y))} else {None})).unwrap();
 
-    assert_eq!((start_x, start_y), (size / 2, size / 2), "Must start at center");
-
-    let mut sum = 0;
-    for entry in get_starts_and_steps(size, 26501365) {
-        sum += count_reachable_after_steps(&grid, entry.start, entry.steps) * entry.count;
+    match count_reachable_infinite(&grid, (start_x, start_y), 26501365) {
+        Some(count) => println!("{}", count),
+        None => println!("The reachable plots never settle into a pattern that's quick enough to find"),
     }
-
-    println!("{}", sum);
 }
 
 fn main() {
     part2();
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    const EXAMPLE: &str = "...........
+.....###.#.
+.###.##..#.
+..#.#...#..
+....#.#....
+.##..S####.
+.##..#...#.
+.......##..
+.##.#.####.
+.##..##.##.
+...........";
+
+    // Counts by searching far enough out that nothing within `steps` can be outside the window.
+    fn count_reachable_brute_force(grid: &Grid<u8>, start: (usize, usize), steps: u64) -> u64 {
+        let tile_size = grid.len().min(grid[0].len()) as u64;
+        let radius = (steps / tile_size + 2) as usize;
+        let distances = TiledDistances::new(grid, start, radius);
+        return distances.distances.iter().filter(|d| **d != UNREACHABLE && is_reachable_in(**d as u64, steps)).count() as u64;
+    }
+
+    fn example_grid() -> Grid<u8> {
+        return EXAMPLE.lines().map(|l| l.bytes().collect()).collect();
+    }
+
+    #[test]
+    fn test_reachable_count() {
+        let distances = bfs_distances(&example_grid(), (5, 5));
+        assert_eq!(distances[5][5], Some(0));
+        assert_eq!(distances[1][5], None);
+
+        assert_eq!(reachable_count(&distances, 0), 1);
+        assert_eq!(reachable_count(&distances, 1), 2);
+        assert_eq!(reachable_count(&distances, 2), 4);
+        assert_eq!(reachable_count(&distances, 6), 16);
+    }
+
+    #[test]
+    fn test_count_terms() {
+        // 3, 8, 13, 18: only 8 and 18 have the same parity as 20.
+        assert_eq!(count_terms(3, 5, 20), 2);
+        assert_eq!(count_terms(4, 2, 9), 0);
+        assert_eq!(count_terms(4, 2, 10), 4);
+        assert_eq!(count_terms(11, 2, 10), 0);
+    }
+
+    #[test]
+    fn test_count_reachable_infinite() {
+        let grid = example_grid();
+        for (steps, expected) in [(6, 16), (10, 50), (50, 1594), (100, 6536), (500, 167004), (1000, 668697), (5000, 16733044)] {
+            assert_eq!(count_reachable_infinite(&grid, (5, 5), steps), Some(expected), "{} steps", steps);
+        }
+    }
+
+    #[test]
+    fn test_count_reachable_off_center() {
+        let mut grid = example_grid();
+        grid[5][5] = b'.';
+        // Cut off one row so the tile isn't square either.
+        grid.pop();
+
+        for start in [(0, 0), (2, 7), (10, 3)] {
+            for steps in [1, 7, 20, 33, 64, 101] {
+                assert_eq!(count_reachable_infinite(&grid, start, steps), Some(count_reachable_brute_force(&grid, start, steps)),
+                    "start {:?}, {} steps", start, steps);
+            }
+        }
+    }
+
+    #[test]
+    fn test_count_reachable_quadratic() {
+        let grid = example_grid();
+        assert_eq!(count_reachable_quadratic(&grid, (5, 5), 500), Some(167004));
+
+        // No clear rows or columns, so paths have to weave around the rocks.
+        let grid: Grid<u8> = ["..#", "...", "#..", ".#."].iter().map(|l| l.bytes().collect()).collect();
+        assert_eq!(count_reachable_quadratic(&grid, (0, 0), 100), Some(5826));
+        assert_eq!(count_reachable_quadratic(&grid, (0, 0), 1000), Some(count_reachable_brute_force(&grid, (0, 0), 1000)));
+        assert_eq!(count_reachable_infinite(&grid, (0, 0), 100), Some(5826));
+    }
+
+    #[test]
+    fn test_count_reachable_full_size_without_lanes() {
+        // A puzzle-sized grid, but with rocks scattered everywhere, so every row and column has to be weaved along.
+        let mut rng = StdRng::seed_from_u64(131);
+        let mut grid: Grid<u8> = (0..131).map(|_| (0..131).map(|_| if rng.gen_range(0..10) == 0 { b'#' } else { b'.' }).collect()).collect();
+        grid[65][65] = b'.';
+        assert!(grid.iter().all(|row| row.contains(&b'#')));
+        assert!((0..131).all(|x| grid.iter().any(|row| row[x] == b'#')));
+
+        // Close enough to count exactly.
+        assert_eq!(count_reachable_infinite(&grid, (65, 65), 500), Some(count_reachable_brute_force(&grid, (65, 65), 500)));
+
+        // Weaving diagonally is cheaper than weaving straight across, so tiles along each axis settle into
+        // different shifts depending on how far off the axis they are, and the count takes too long to repeat. This gives up rather than running out of memory.
+        assert_eq!(count_reachable_infinite(&grid, (65, 65), 26501365), None);
+    }
+
+    #[test]
+    fn test_count_reachable_random() {
+        let mut rng = StdRng::seed_from_u64(21);
+
+        for _ in 0..300 {
+            let width = rng.gen_range(2..6);
+            let height = rng.gen_range(2..6);
+            let mut grid: Grid<u8> = (0..height).map(|_| (0..width).map(|_| if rng.gen_range(0..4) == 0 { b'#' } else { b'.' }).collect()).collect();
+            let start = (rng.gen_range(0..width), rng.gen_range(0..height));
+            grid[start.1][start.0] = b'.';
+            let steps = rng.gen_range(0..200);
+
+            let rows: Vec<String> = grid.iter().map(|row| String::from_utf8(row.clone()).unwrap()).collect();
+            assert_eq!(count_reachable_infinite(&grid, start, steps), Some(count_reachable_brute_force(&grid, start, steps)),
+                "{:?}, start {:?}, {} steps", rows, start, steps);
+        }
+    }
+}
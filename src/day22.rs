@@ -1,7 +1,12 @@
+use std::env;
+use std::fmt;
 use std::fs;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
 
 type Grid<T> = Vec<Vec<T>>;
 
+#[derive(Clone, Debug, PartialEq)]
 struct Brick {
     start: Vec<usize>,
     end: Vec<usize>
@@ -15,110 +20,675 @@ impl Brick {
     fn height(&self) -> usize {
         return self.end[2] - self.start[2] + 1;
     }
+
+    fn cells(&self) -> impl Iterator<Item=Cell> + '_ {
+        (self.start[2]..=self.end[2]).flat_map(move |z| self.xy_coords().map(move |(x, y)| (x, y, z)))
+    }
+
+    fn move_to_z(&mut self, z: usize) {
+        let height = self.height();
+        self.start[2] = z;
+        self.end[2] = z + height - 1;
+    }
 }
 
 fn parse_tuple(tuple_str: &str) -> Option<Vec<usize>> {
     let parts: Result<Vec<usize>, _> = tuple_str.split(",").map(|s| s.parse()).collect();
     if let Ok(some_parts) = parts {
-        return Some(some_parts);
+        if some_parts.len() == 3 {
+            return Some(some_parts);
+        }
     }
 
     return None;
 }
 
-fn parse_brick(line: &str) -> Brick {
-    let (start_str, end_str) = line.split_once("~").unwrap();
+// Line numbers start at 1.
+#[derive(Debug, PartialEq)]
+enum BrickError {
+    MissingTilde { line: usize },
+    BadCoordinates { line: usize, coords: String },
+    NotStraight { line: usize },
+    BelowGround { line: usize },
+    Overlap { line: usize, other_line: usize, cell: Cell },
+}
 
-    return Brick {
-        start: parse_tuple(start_str).unwrap(),
-        end: parse_tuple(end_str).unwrap()
+impl fmt::Display for BrickError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BrickError::MissingTilde { line } => write!(f, "line {}: expected two coordinates separated by '~'", line),
+            BrickError::BadCoordinates { line, coords } => write!(f, "line {}: expected x,y,z with non-negative integers, got '{}'", line, coords),
+            BrickError::NotStraight { line } => write!(f, "line {}: brick ends differ along more than one axis", line),
+            BrickError::BelowGround { line } => write!(f, "line {}: brick reaches z = 0, which is the ground", line),
+            BrickError::Overlap { line, other_line, cell } => write!(f, "line {}: brick overlaps the brick on line {} at {:?}", line, other_line, cell),
+        }
     }
 }
 
-#[derive(Clone, Debug)]
-struct BrickGridEntry {
-    z: usize,
-    brick_i: usize
-}
+// Ends may be given in either order; the brick always ends up with start <= end on every axis.
+fn parse_brick(line_number: usize, line: &str) -> Result<Brick, BrickError> {
+    let (start_str, end_str) = line.split_once("~").ok_or(BrickError::MissingTilde { line: line_number })?;
 
-fn part1(bricks_above_brick: &Vec<Vec<usize>>, bricks_below_brick: &Vec<Vec<usize>>) -> usize {
-    let mut count = 0;
-    for i in 0..bricks_above_brick.len() {
-        let can_remove = bricks_above_brick[i].iter().all(|brick_above| {
-            return bricks_below_brick[*brick_above].len() > 1;
-        });
+    let bad_coordinates = |coords: &str| BrickError::BadCoordinates { line: line_number, coords: coords.to_owned() };
+    let a = parse_tuple(start_str).ok_or_else(|| bad_coordinates(start_str))?;
+    let b = parse_tuple(end_str).ok_or_else(|| bad_coordinates(end_str))?;
 
-        if can_remove {
-            count += 1;
-        }
+    if (0..3).filter(|axis| a[*axis] != b[*axis]).count() > 1 {
+        return Err(BrickError::NotStraight { line: line_number });
+    }
+
+    let brick = Brick {
+        start: (0..3).map(|axis| a[axis].min(b[axis])).collect(),
+        end: (0..3).map(|axis| a[axis].max(b[axis])).collect()
+    };
+
+    if brick.start[2] == 0 {
+        return Err(BrickError::BelowGround { line: line_number });
     }
-    return count;
+
+    return Ok(brick);
 }
 
-fn chain_disintegrate(bricks_above_brick: &Vec<Vec<usize>>, bricks_below_brick: &Vec<Vec<usize>>, on_ground: &Vec<bool>, brick: usize) -> usize {
-    let n = bricks_above_brick.len();
-    let mut disintigrated = vec![false; n];
-    disintigrated[brick] = true;
-    let mut cnt = 0;
+// Parses a snapshot of falling bricks, checking that no two bricks share a cube.
+fn parse_bricks(content: &str) -> Result<Vec<Brick>, BrickError> {
+    let mut bricks: Vec<Brick> = vec![];
+    let mut occupied: HashMap<Cell, usize> = HashMap::new();
 
-    let mut progress = true;
-    while progress {
-        progress = false;
-        for i in 0..n {
-            if !disintigrated[i] && !on_ground[i] && bricks_below_brick[i].iter().all(|b| disintigrated[*b]) {
-                disintigrated[i] = true;
-                progress = true;
-                cnt += 1;
+    for (i, line) in content.lines().enumerate() {
+        let brick = parse_brick(i + 1, line)?;
+        for cell in brick.cells() {
+            if let Some(other_line) = occupied.insert(cell, i + 1) {
+                return Err(BrickError::Overlap { line: i + 1, other_line: other_line, cell: cell });
             }
         }
+        bricks.push(brick);
     }
 
-    return cnt;
+    return Ok(bricks);
 }
 
-fn part2(bricks_above_brick: &Vec<Vec<usize>>, bricks_below_brick: &Vec<Vec<usize>>, on_ground: &Vec<bool>) -> usize {
-    let mut sum = 0;
-    for i in 0..bricks_above_brick.len() {
-        sum += chain_disintegrate(bricks_above_brick, bricks_below_brick, on_ground, i);
-    }
-    return sum;
+type Cell = (usize, usize, usize);
+
+#[derive(Debug, PartialEq)]
+struct Move {
+    brick_i: usize,
+    distance: usize
 }
 
-fn main() {
-    let content = fs::read_to_string("./src/input22.txt").unwrap();
+// Bricks keep the index they were given in the input (added bricks get the next index, and a removed brick's index
+// is never reused). After settle(), each brick knows which bricks it rests on and how many bricks would fall if it
+// were disintegrated.
+struct BrickStack {
+    // None once a brick has been removed.
+    bricks: Vec<Option<Brick>>,
+    occupied: HashMap<Cell, usize>,
+    supports: Vec<Vec<usize>>,
+    supported_by: Vec<Vec<usize>>,
+    // Immediate dominator of each brick in the support graph (see compute_dominators), len() for the ground.
+    idoms: Vec<usize>,
+    fall_counts: Vec<usize>,
+}
+
+// Number of levels in the binary lifting table, enough for 2^32 bricks stacked on top of each other.
+const LOG_DEPTH: usize = 32;
+
+impl BrickStack {
+    fn new(bricks: Vec<Brick>) -> BrickStack {
+        return BrickStack {
+            bricks: bricks.into_iter().map(Some).collect(),
+            occupied: HashMap::new(),
+            supports: vec![],
+            supported_by: vec![],
+            idoms: vec![],
+            fall_counts: vec![],
+        };
+    }
+
+    // One past the largest brick index, including removed bricks.
+    fn len(&self) -> usize {
+        return self.bricks.len();
+    }
 
-    let mut bricks: Vec<_> = content.lines().map(parse_brick).collect();
-    bricks.sort_by_key(|b| b.start[2]);
+    fn brick(&self, i: usize) -> &Brick {
+        return self.bricks[i].as_ref().expect("Brick has been removed");
+    }
+
+    // Indices of the bricks that haven't been removed.
+    fn indices(&self) -> impl Iterator<Item=usize> + '_ {
+        return (0..self.len()).filter(|i| self.bricks[*i].is_some());
+    }
+
+    // Bricks sorted from the lowest to the highest. Since bricks only fall straight down,
+    // a brick can only ever rest on bricks that come before it in this order.
+    fn bottom_up_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = self.indices().collect();
+        order.sort_by_key(|i| self.brick(*i).start[2]);
+        return order;
+    }
+
+    // Lets every brick fall as far as it can, then works out what supports what.
+    fn settle(&mut self) {
+        let width = self.indices().map(|i| self.brick(i).start[0].max(self.brick(i).end[0])).max().unwrap_or(0) + 1;
+        let height = self.indices().map(|i| self.brick(i).start[1].max(self.brick(i).end[1])).max().unwrap_or(0) + 1;
 
-    let ground_idx = bricks.len() + 1;
+        // The top of the highest brick so far in each column.
+        let mut grid: Grid<usize> = vec![vec![0; width]; height];
 
-    let width = bricks.iter().map(|b| b.start[0]).max().unwrap() + 1;
-    let height = bricks.iter().map(|b| b.start[1]).max().unwrap() + 1;
+        for i in self.bottom_up_order() {
+            let brick = self.brick(i);
+            let max_z = brick.xy_coords().map(|(x, y)| grid[y][x]).max().unwrap();
 
-    let mut bricks_above_brick: Vec<Vec<usize>> = vec![vec![]; bricks.len()];
-    let mut bricks_below_brick: Vec<Vec<usize>> = vec![vec![]; bricks.len()];
-    let mut on_ground: Vec<bool> = vec![false; bricks.len()];
+            for (x, y) in brick.xy_coords() {
+                grid[y][x] = max_z + brick.height();
+            }
+
+            self.bricks[i].as_mut().unwrap().move_to_z(max_z + 1);
+        }
 
-    let mut grid: Grid<BrickGridEntry> = vec![vec![BrickGridEntry {z: 0, brick_i: ground_idx}; width]; height];
+        self.occupied = HashMap::new();
+        for i in 0..self.len() {
+            if let Some(brick) = &self.bricks[i] {
+                for cell in brick.cells() {
+                    self.occupied.insert(cell, i);
+                }
+            }
+        }
 
-    for (i, brick) in bricks.iter().enumerate() {
-        let max_z = brick.xy_coords().map(|(x, y)| grid[y][x].z).max().unwrap();
+        self.link();
+    }
+
+    // Bricks whose bottom touches the top of brick i.
+    fn bricks_on_top(&self, i: usize) -> Vec<usize> {
+        let brick = self.brick(i);
+        let mut on_top: Vec<usize> = vec![];
         for (x, y) in brick.xy_coords() {
-            let entry = &grid[y][x];
-            if entry.z == max_z {
-                if entry.brick_i == ground_idx {
-                    on_ground[i] = true;
+            if let Some(j) = self.occupied.get(&(x, y, brick.end[2] + 1)) {
+                if *j != i && !on_top.contains(j) {
+                    on_top.push(*j);
                 }
-                if entry.brick_i != ground_idx && !bricks_below_brick[i].contains(&entry.brick_i) {
-                    bricks_below_brick[i].push(entry.brick_i);
-                    bricks_above_brick[entry.brick_i].push(i);
+            }
+        }
+        return on_top;
+    }
+
+    // Bricks whose top touches the bottom of brick i.
+    fn bricks_underneath(&self, i: usize) -> Vec<usize> {
+        let brick = self.brick(i);
+        let mut underneath: Vec<usize> = vec![];
+        for (x, y) in brick.xy_coords() {
+            if let Some(j) = self.occupied.get(&(x, y, brick.start[2] - 1)) {
+                if *j != i && !underneath.contains(j) {
+                    underneath.push(*j);
                 }
             }
+        }
+        return underneath;
+    }
 
-            grid[y][x] = BrickGridEntry {z: max_z + brick.height(), brick_i: i};
+    // Recomputes the support graph and fall counts from where the bricks are now.
+    fn link(&mut self) {
+        self.supports = vec![vec![]; self.len()];
+        self.supported_by = vec![vec![]; self.len()];
+
+        let order = self.bottom_up_order();
+        for i in order.iter().copied() {
+            for j in self.bricks_underneath(i) {
+                self.supported_by[i].push(j);
+                self.supports[j].push(i);
+            }
         }
+
+        self.idoms = self.compute_dominators(&order);
+
+        let mut subtree_sizes = vec![1; self.len() + 1];
+        for i in order.iter().rev().copied() {
+            subtree_sizes[self.idoms[i]] += subtree_sizes[i];
+        }
+        self.fall_counts = (0..self.len()).map(|i| subtree_sizes[i] - 1).collect();
+    }
+
+    // Lets the given bricks (and anything that was resting on a brick that moves) fall as far as they can.
+    // Only bricks that could actually have moved are looked at.
+    fn drop_bricks(&mut self, candidates: Vec<usize>) -> Vec<Move> {
+        let mut queue: BinaryHeap<Reverse<(usize, usize)>> = candidates.iter().map(|i| Reverse((self.brick(*i).start[2], *i))).collect();
+        let mut queued: HashSet<usize> = candidates.into_iter().collect();
+        let mut moves: Vec<Move> = vec![];
+
+        // Lowest first, so everything underneath a brick has finished falling before we move it.
+        while let Some(Reverse((_, i))) = queue.pop() {
+            queued.remove(&i);
+
+            let brick = self.brick(i);
+            let landing_z = brick.xy_coords().map(|(x, y)| {
+                let mut z = brick.start[2];
+                while z > 1 && !self.occupied.contains_key(&(x, y, z - 1)) {
+                    z -= 1;
+                }
+                z
+            }).max().unwrap();
+
+            if landing_z == brick.start[2] {
+                continue;
+            }
+
+            let distance = brick.start[2] - landing_z;
+            let old_cells: Vec<Cell> = brick.cells().collect();
+            let on_top = self.bricks_on_top(i);
+
+            for cell in old_cells {
+                self.occupied.remove(&cell);
+            }
+            self.bricks[i].as_mut().unwrap().move_to_z(landing_z);
+            let new_cells: Vec<Cell> = self.brick(i).cells().collect();
+            for cell in new_cells {
+                self.occupied.insert(cell, i);
+            }
+            moves.push(Move { brick_i: i, distance: distance });
+
+            for j in on_top {
+                if queued.insert(j) {
+                    queue.push(Reverse((self.brick(j).start[2], j)));
+                }
+            }
+        }
+
+        moves.sort_by_key(|m| m.brick_i);
+        return moves;
+    }
+
+    // Takes brick i out of the stack and lets everything above it fall. Returns every brick that moved.
+    fn remove(&mut self, i: usize) -> Vec<Move> {
+        let on_top = self.bricks_on_top(i);
+        let cells: Vec<Cell> = self.brick(i).cells().collect();
+        for cell in cells {
+            self.occupied.remove(&cell);
+        }
+        self.bricks[i] = None;
+
+        let moves = self.drop_bricks(on_top);
+        self.link();
+        return moves;
     }
 
-    println!("{}", part1(&bricks_above_brick, &bricks_below_brick));
-    println!("{}", part2(&bricks_above_brick, &bricks_below_brick, &on_ground));
-}
\ No newline at end of file
+    // Puts a new brick at the given position and lets it fall. Returns its index and how far it fell.
+    // Errors count the new brick as the next line of the snapshot, and leave the stack as it was.
+    fn add(&mut self, brick: Brick) -> Result<(usize, Vec<Move>), BrickError> {
+        let i = self.len();
+        if let Some((cell, other)) = brick.cells().find_map(|cell| self.occupied.get(&cell).map(|other| (cell, *other))) {
+            return Err(BrickError::Overlap { line: i + 1, other_line: other + 1, cell: cell });
+        }
+
+        for cell in brick.cells() {
+            self.occupied.insert(cell, i);
+        }
+        self.bricks.push(Some(brick));
+
+        let moves = self.drop_bricks(vec![i]);
+        self.link();
+        return Ok((i, moves));
+    }
+
+    // Bricks resting directly on brick i.
+    fn supports(&self, i: usize) -> &[usize] {
+        return &self.supports[i];
+    }
+
+    // Bricks that brick i rests directly on. Empty if it's on the ground.
+    fn supported_by(&self, i: usize) -> &[usize] {
+        return &self.supported_by[i];
+    }
+
+    // Bricks that can be disintegrated without anything else falling.
+    fn safe_to_remove(&self) -> Vec<usize> {
+        return self.indices()
+            .filter(|i| self.supports(*i).iter().all(|above| self.supported_by(*above).len() > 1))
+            .collect();
+    }
+
+    // How many other bricks would fall if brick i were disintegrated.
+    fn fall_count(&self, i: usize) -> usize {
+        return self.fall_counts[i];
+    }
+
+    // The bricks that would fall if brick i were disintegrated.
+    fn falling_bricks(&self, i: usize) -> Vec<usize> {
+        let mut falls = vec![false; self.len() + 1];
+        falls[i] = true;
+
+        let mut falling: Vec<usize> = vec![];
+        for j in self.bottom_up_order() {
+            if j != i && falls[self.idoms[j]] {
+                falls[j] = true;
+                falling.push(j);
+            }
+        }
+
+        falling.sort();
+        return falling;
+    }
+
+    // Think of the ground as the root of the support graph. Brick j falls when brick i goes exactly when
+    // every chain of support from the ground to j passes through i, i.e. when i dominates j.
+    // The graph is acyclic, so each brick's immediate dominator is the lowest common ancestor (in the dominator tree)
+    // of the bricks it rests on, and the bricks that fall with i are i's subtree.
+    fn compute_dominators(&self, order: &[usize]) -> Vec<usize> {
+        let n = self.len();
+        let ground = n;
+
+        let mut depth = vec![0; n + 1];
+        // up[k][v] is the 2^k-th ancestor of v in the dominator tree. The ground is its own parent.
+        let mut up: Vec<Vec<usize>> = vec![vec![ground; n + 1]; LOG_DEPTH];
+
+        let lca = |up: &Vec<Vec<usize>>, depth: &Vec<usize>, mut a: usize, mut b: usize| -> usize {
+            if depth[a] < depth[b] {
+                (a, b) = (b, a);
+            }
+            for k in (0..LOG_DEPTH).rev() {
+                if depth[a] - depth[b] >= 1 << k {
+                    a = up[k][a];
+                }
+            }
+            if a == b {
+                return a;
+            }
+            for k in (0..LOG_DEPTH).rev() {
+                if up[k][a] != up[k][b] {
+                    a = up[k][a];
+                    b = up[k][b];
+                }
+            }
+            return up[0][a];
+        };
+
+        for i in order.iter().copied() {
+            let idom = self.supported_by[i].iter().copied()
+                .reduce(|a, b| lca(&up, &depth, a, b))
+                .unwrap_or(ground);
+
+            depth[i] = depth[idom] + 1;
+            up[0][i] = idom;
+            for k in 1..LOG_DEPTH {
+                up[k][i] = up[k - 1][up[k - 1][i]];
+            }
+        }
+
+        return up[0][0..n].to_vec();
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Highlight {
+    Normal,
+    Disintegrated,
+    Falls,
+}
+
+fn highlights(stack: &BrickStack, disintegrate: Option<usize>) -> Vec<Highlight> {
+    let mut highlights = vec![Highlight::Normal; stack.len()];
+    if let Some(i) = disintegrate {
+        highlights[i] = Highlight::Disintegrated;
+        for j in stack.falling_bricks(i) {
+            highlights[j] = Highlight::Falls;
+        }
+    }
+    return highlights;
+}
+
+// One voxel per line: "x y z brick mark". The mark is X for the disintegrated brick, F for the bricks that would fall
+// with it, and . for everything else.
+fn to_voxels(stack: &BrickStack, disintegrate: Option<usize>) -> String {
+    let highlights = highlights(stack, disintegrate);
+
+    let mut lines = vec!["# x y z brick mark".to_owned()];
+    for i in stack.indices() {
+        let mark = match highlights[i] {
+            Highlight::Normal => ".",
+            Highlight::Disintegrated => "X",
+            Highlight::Falls => "F",
+        };
+        for (x, y, z) in stack.brick(i).cells() {
+            lines.push(format!("{} {} {} {} {}", x, y, z, i, mark));
+        }
+    }
+
+    return lines.join("\n");
+}
+
+// Spreads the hues out using the golden ratio, so neighboring indices get very different colors.
+fn brick_color(i: usize) -> (f64, f64, f64) {
+    let hue = (i as f64 * 0.618033988749895).fract() * 6.0;
+    let x = 1.0 - (hue % 2.0 - 1.0).abs();
+    return match hue as usize {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+}
+
+// Corners of a box, as bits: 1 for the far x side, 2 for far y, 4 for far z. Each face is listed counterclockwise
+// when seen from outside.
+const BOX_FACES: [[usize; 4]; 6] = [[0, 4, 6, 2], [1, 3, 7, 5], [0, 1, 5, 4], [2, 6, 7, 3], [0, 2, 3, 1], [4, 5, 7, 6]];
+
+// Wavefront OBJ with one box object per brick, named brick_<index>. Colors are given per vertex ("v x y z r g b"),
+// which most viewers understand. When disintegrating a brick, it is red, the bricks that would fall are yellow
+// and everything else is grey.
+fn to_obj(stack: &BrickStack, disintegrate: Option<usize>) -> String {
+    let highlights = highlights(stack, disintegrate);
+
+    let mut lines: Vec<String> = vec![];
+    let mut num_vertices = 0;
+    for i in stack.indices() {
+        let brick = stack.brick(i);
+        let (r, g, b) = match highlights[i] {
+            Highlight::Disintegrated => (1.0, 0.0, 0.0),
+            Highlight::Falls => (1.0, 1.0, 0.0),
+            Highlight::Normal if disintegrate.is_some() => (0.5, 0.5, 0.5),
+            Highlight::Normal => brick_color(i),
+        };
+
+        lines.push(format!("o brick_{}", i));
+        for corner in 0..8 {
+            let coords: Vec<usize> = (0..3).map(|axis| {
+                if corner & (1 << axis) != 0 { brick.end[axis] + 1 } else { brick.start[axis] }
+            }).collect();
+            lines.push(format!("v {} {} {} {:.3} {:.3} {:.3}", coords[0], coords[1], coords[2], r, g, b));
+        }
+        for face in BOX_FACES.iter() {
+            let idxs: Vec<String> = face.iter().map(|corner| (num_vertices + corner + 1).to_string()).collect();
+            lines.push(format!("f {}", idxs.join(" ")));
+        }
+        num_vertices += 8;
+    }
+
+    return lines.join("\n");
+}
+
+fn part1(stack: &BrickStack) -> usize {
+    return stack.safe_to_remove().len();
+}
+
+fn part2(stack: &BrickStack) -> usize {
+    return stack.indices().map(|i| stack.fall_count(i)).sum();
+}
+
+fn print_moves(stack: &BrickStack, moves: &[Move]) {
+    for m in moves {
+        println!("Brick {} falls {} to z = {}", m.brick_i, m.distance, stack.brick(m.brick_i).start[2]);
+    }
+    println!("{}", part1(stack));
+    println!("{}", part2(stack));
+}
+
+// Usage: day22 [remove <brick> | add <x,y,z~x,y,z> | <export_path_prefix> [brick_to_disintegrate]]
+// remove and add change the settled stack, then print every brick that fell and both answers for the new stack.
+// Otherwise, writes the settled stack to <prefix>.vox.txt and <prefix>.obj.
+fn main() {
+    let content = fs::read_to_string("./src/input22.txt").unwrap();
+
+    let bricks = match parse_bricks(&content) {
+        Ok(bricks) => bricks,
+        Err(err) => {
+            println!("Invalid snapshot: {}", err);
+            return;
+        }
+    };
+    let mut stack = BrickStack::new(bricks);
+    stack.settle();
+
+    println!("{}", part1(&stack));
+    println!("{}", part2(&stack));
+
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(|a| a.as_str()) {
+        Some("remove") => {
+            let moves = stack.remove(args[2].parse().unwrap());
+            print_moves(&stack, &moves);
+        },
+        Some("add") => match parse_brick(stack.len() + 1, &args[2]).and_then(|brick| stack.add(brick)) {
+            Ok((i, moves)) => {
+                println!("Added brick {}", i);
+                print_moves(&stack, &moves);
+            },
+            Err(err) => println!("Can't add brick: {}", err),
+        },
+        Some(prefix) => {
+            let disintegrate: Option<usize> = args.get(2).map(|i| i.parse().unwrap());
+            fs::write(format!("{}.vox.txt", prefix), to_voxels(&stack, disintegrate)).unwrap();
+            fs::write(format!("{}.obj", prefix), to_obj(&stack, disintegrate)).unwrap();
+        },
+        None => {},
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "1,0,1~1,2,1
+0,0,2~2,0,2
+0,2,3~2,2,3
+0,0,4~0,2,4
+2,0,5~2,2,5
+0,1,6~2,1,6
+1,1,8~1,1,9";
+
+    fn example_stack() -> BrickStack {
+        let mut stack = BrickStack::new(parse_bricks(EXAMPLE).unwrap());
+        stack.settle();
+        return stack;
+    }
+
+    #[test]
+    fn test_parse_brick() {
+        assert_eq!(parse_brick(1, "2,0,5~0,0,5"), Ok(Brick { start: vec![0, 0, 5], end: vec![2, 0, 5] }));
+        assert_eq!(parse_brick(2, "0,0,5"), Err(BrickError::MissingTilde { line: 2 }));
+        assert_eq!(parse_brick(3, "0,0,5~0,x,5"), Err(BrickError::BadCoordinates { line: 3, coords: "0,x,5".to_owned() }));
+        assert_eq!(parse_brick(4, "0,0,5~1,1,5"), Err(BrickError::NotStraight { line: 4 }));
+        assert_eq!(parse_brick(5, "0,0,0~0,0,1"), Err(BrickError::BelowGround { line: 5 }));
+    }
+
+    #[test]
+    fn test_parse_bricks_overlap() {
+        let result = parse_bricks("0,0,1~2,0,1\n1,1,1~1,1,1\n1,0,3~1,0,1");
+        assert_eq!(result, Err(BrickError::Overlap { line: 3, other_line: 1, cell: (1, 0, 1) }));
+    }
+
+    #[test]
+    fn test_settle_bounds_from_both_ends() {
+        let mut stack = BrickStack::new(parse_bricks("0,0,2~5,0,2\n0,3,1~0,0,1").unwrap());
+        stack.settle();
+
+        assert_eq!(stack.supported_by(0), &[1]);
+    }
+
+    #[test]
+    fn test_settle() {
+        let stack = example_stack();
+
+        assert_eq!(stack.supports(0), &[1, 2]);
+        assert_eq!(stack.supported_by(3), &[1, 2]);
+        assert_eq!(stack.supported_by(0), &[] as &[usize]);
+        assert_eq!(stack.brick(6).start[2], 5);
+        assert_eq!(stack.brick(6).end[2], 6);
+    }
+
+    #[test]
+    fn test_safe_to_remove() {
+        assert_eq!(example_stack().safe_to_remove(), vec![1, 2, 3, 4, 6]);
+    }
+
+    #[test]
+    fn test_fall_count() {
+        let stack = example_stack();
+        let fall_counts: Vec<_> = (0..stack.len()).map(|i| stack.fall_count(i)).collect();
+
+        assert_eq!(fall_counts, vec![6, 0, 0, 0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn test_falling_bricks() {
+        let stack = example_stack();
+
+        assert_eq!(stack.falling_bricks(0), vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(stack.falling_bricks(5), vec![6]);
+        assert_eq!(stack.falling_bricks(6), vec![]);
+    }
+
+    #[test]
+    fn test_to_voxels() {
+        let voxels = to_voxels(&example_stack(), Some(5));
+        let lines: Vec<_> = voxels.lines().collect();
+
+        assert_eq!(lines.len(), 1 + 3 * 6 + 2);
+        assert_eq!(lines[1], "1 0 1 0 .");
+        assert!(lines.contains(&"1 1 4 5 X"));
+        assert!(lines.contains(&"1 1 6 6 F"));
+    }
+
+    #[test]
+    fn test_to_obj() {
+        let obj = to_obj(&example_stack(), None);
+
+        assert_eq!(obj.lines().filter(|l| l.starts_with("o ")).count(), 7);
+        assert_eq!(obj.lines().filter(|l| l.starts_with("v ")).count(), 7 * 8);
+        assert!(obj.contains("o brick_6\nv 1 1 5 "));
+        assert!(obj.ends_with("f 53 54 56 55"));
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut stack = example_stack();
+
+        assert_eq!(stack.remove(1), vec![]);
+        assert_eq!(stack.remove(2), vec![Move { brick_i: 3, distance: 2 }, Move { brick_i: 4, distance: 2 },
+            Move { brick_i: 5, distance: 2 }, Move { brick_i: 6, distance: 2 }]);
+        assert_eq!(stack.supported_by(3), &[] as &[usize]);
+        assert_eq!(stack.supported_by(5), &[3, 0, 4]);
+        assert_eq!(stack.fall_count(0), 0);
+        assert_eq!(stack.indices().count(), 5);
+    }
+
+    #[test]
+    fn test_add() {
+        let mut stack = example_stack();
+
+        let (i, moves) = stack.add(parse_brick(1, "1,1,20~1,1,20").unwrap()).unwrap();
+        assert_eq!(i, 7);
+        assert_eq!(moves, vec![Move { brick_i: 7, distance: 13 }]);
+        assert_eq!(stack.supported_by(7), &[6]);
+        assert_eq!(stack.fall_count(6), 1);
+    }
+
+    #[test]
+    fn test_add_overlap() {
+        let mut stack = example_stack();
+
+        // Brick 6 settles at 1,1,5~1,1,6.
+        assert_eq!(stack.add(parse_brick(1, "0,1,6~2,1,6").unwrap()), Err(BrickError::Overlap { line: 8, other_line: 7, cell: (1, 1, 6) }));
+        assert_eq!(stack.len(), 7);
+        assert_eq!(stack.add(parse_brick(1, "0,1,7~2,1,7").unwrap()).map(|(i, _)| i), Ok(7));
+    }
+}
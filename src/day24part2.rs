@@ -364,88 +364,194 @@ mod tests {
 
         assert_eq!(result, expected);
     }
+
+    const EXAMPLE: &str = "19, 13, 30 @ -2, 1, -2
+18, 19, 22 @ -1, -1, -2
+20, 25, 34 @ -2, -2, -4
+12, 31, 28 @ -1, -2, -1
+20, 19, 15 @ 1, -5, -3";
+
+    #[test]
+    fn test_find_rock() {
+        let stones: Vec<_> = EXAMPLE.lines().map(super::parse_stone).collect();
+        let rock = super::find_rock(&stones).unwrap();
+
+        assert_eq!(rock.position, vec![24, 13, 10]);
+        assert_eq!(rock.velocity, vec![-3, 1, 2]);
+    }
+
+    #[test]
+    fn test_find_rock_misses() {
+        let mut stones: Vec<_> = EXAMPLE.lines().map(super::parse_stone).collect();
+        stones.push(super::parse_stone("1, 2, 3 @ 0, 0, 0"));
+
+        assert!(matches!(super::find_rock(&stones), Err(super::ThrowError::MissesStone(5))));
+    }
+
+    #[test]
+    fn test_find_rock_parallel() {
+        let stones: Vec<_> = ["0, 0, 0 @ 1, 1, 1", "1, 0, 0 @ 1, 1, 1", "2, 0, 0 @ 1, 1, 1"].into_iter().map(super::parse_stone).collect();
+
+        assert!(matches!(super::find_rock(&stones), Err(super::ThrowError::NoIndependentStones)));
+    }
+
+    #[test]
+    fn test_find_rock_inconsistent() {
+        // No throw hits all of the first three stones, so there is no need to look any further.
+        let stones: Vec<_> = ["2, 0, 3 @ 0, 0, 0", "2, 3, 0 @ 1, -1, -1", "0, 3, 2 @ -1, 1, 1", "5, 5, 5 @ 1, 2, 3"].into_iter().map(super::parse_stone).collect();
+
+        assert!(matches!(super::find_rock(&stones), Err(super::ThrowError::NoThrowHitsAll(0, 1, 2))));
+    }
 }
 
+use std::fmt;
+
 use linear_equations::*;
 use rationals::Rational;
 use rles::*;
 
-fn make_equation<'a>(
-    pa: &'a Variable,
-    pb: &'a Variable,
-    va: &'a Variable,
-    vb: &'a Variable,
-    pavb: &'a Variable,
-    pbva: &'a Variable,
-    pai: &Rational,
-    pbi: &Rational,
-    vai: &Rational,
-    vbi: &Rational) -> Equation<'a>
-    {
-    let one = Rational::int(1);
-    let neg_one = Rational::int(-1);
-
-    return Equation {
-        lhs: vec![
-            Term::new(one, pavb),
-            Term::new(-pai.clone(), vb),
-            Term::new(-vbi.clone(), pa),
-
-            Term::new(neg_one, pbva),
-            Term::new(pbi.clone(), va),
-            Term::new(vai.clone(), pb),
-        ],
-        rhs: pbi.clone() * vai.clone() - pai.clone() * vbi.clone()
-    };
+struct Rock {
+    position: Vec<i64>,
+    velocity: Vec<i64>
+}
+
+#[derive(Debug)]
+enum ThrowError {
+    NotEnoughStones,
+    NoIndependentStones,
+    NoThrowHitsAll(usize, usize, usize),
+    NonIntegerSolution(Vec<Rational>),
+    MissesStone(usize),
+}
+
+impl fmt::Display for ThrowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ThrowError::NotEnoughStones => write!(f, "need at least 3 hailstones to pin down the rock"),
+            ThrowError::NoIndependentStones => write!(f, "no 3 hailstones give an independent system of equations"),
+            ThrowError::NoThrowHitsAll(a, b, c) => write!(f, "no throw can hit hailstones {}, {} and {} (lines {}, {} and {})", a, b, c, a + 1, b + 1, c + 1),
+            ThrowError::NonIntegerSolution(solution) => write!(f, "the only possible throw is not at integer coordinates: {:?}", solution),
+            ThrowError::MissesStone(i) => write!(f, "the only possible throw misses hailstone {} (line {})", i, i + 1),
+        }
+    }
+}
+
+fn int(n: i64) -> Rational {
+    return Rational::int(n);
+}
+
+// The rock (p, v) hits stone i iff (p - pi) x (v - vi) = 0. Expanding gives p x v - p x vi - pi x v + pi x vi = 0.
+// The p x v term is the same for every stone, so subtracting the equations for stones a and b leaves a linear system:
+//     p x (vb - va) + (pb - pa) x v = pb x vb - pa x va
+// Each pair contributes 3 equations (one per component of the cross product).
+fn pair_equations<'a>(p: &'a [Variable], v: &'a [Variable], a: &Stone, b: &Stone) -> Vec<Equation<'a>> {
+    let d: Vec<i64> = (0..3).map(|i| b.velocity[i] - a.velocity[i]).collect();
+    let e: Vec<i64> = (0..3).map(|i| b.position[i] - a.position[i]).collect();
+
+    let mut equations = vec![];
+    for i in 0..3 {
+        let j = (i + 1) % 3;
+        let k = (i + 2) % 3;
+
+        // Component i of u x w is u[j] * w[k] - u[k] * w[j].
+        let cross_i = |u: &Vec<i64>, w: &Vec<i64>| int(u[j]) * int(w[k]) - int(u[k]) * int(w[j]);
+
+        equations.push(Equation {
+            lhs: vec![
+                Term::new(int(d[k]), &p[j]),
+                Term::new(-int(d[j]), &p[k]),
+                Term::new(int(e[j]), &v[k]),
+                Term::new(-int(e[k]), &v[j]),
+            ],
+            rhs: cross_i(&b.position, &b.velocity) - cross_i(&a.position, &a.velocity)
+        });
+    }
+
+    return equations;
+}
+
+// Returns the time at which the rock hits the stone, if it does so at a non-negative integer time.
+fn collision_time(rock: &Rock, stone: &Stone) -> Option<i128> {
+    let mut time: Option<i128> = None;
+    for i in 0..3 {
+        let dp = (rock.position[i] - stone.position[i]) as i128;
+        let dv = (stone.velocity[i] - rock.velocity[i]) as i128;
+
+        if dv == 0 {
+            if dp != 0 {
+                return None;
+            }
+            continue;
+        }
+
+        if dp % dv != 0 {
+            return None;
+        }
+
+        let t = dp / dv;
+        if t < 0 || time.is_some_and(|prev| prev != t) {
+            return None;
+        }
+        time = Some(t);
+    }
+
+    // The rock and stone share a trajectory, so they collide immediately.
+    return Some(time.unwrap_or(0));
+}
+
+// Solves using only the pairs (a, b) and (a, c) for the first 3 stones that give an independent system,
+// then checks that the throw actually works for every stone.
+fn find_rock(stones: &[Stone]) -> Result<Rock, ThrowError> {
+    if stones.len() < 3 {
+        return Err(ThrowError::NotEnoughStones);
+    }
+
+    let p: Vec<_> = ["px", "py", "pz"].iter().enumerate().map(|(idx, name)| Variable { idx: idx, name: name.to_string() }).collect();
+    let v: Vec<_> = ["vx", "vy", "vz"].iter().enumerate().map(|(idx, name)| Variable { idx: idx + 3, name: name.to_string() }).collect();
+
+    for a in 0..stones.len() {
+        for b in a + 1..stones.len() {
+            for c in b + 1..stones.len() {
+                let mut system = pair_equations(&p, &v, &stones[a], &stones[b]);
+                system.extend(pair_equations(&p, &v, &stones[a], &stones[c]));
+
+                let solution = match solve(&system) {
+                    Outcome::Solution(solution) => solution,
+                    // Every throw that hits all the stones solves these equations, so if nothing does, there's no throw.
+                    Outcome::Unsolvable => return Err(ThrowError::NoThrowHitsAll(a, b, c)),
+                    // These stones are (nearly) parallel, so try another set.
+                    Outcome::Underconstrained(_) => continue,
+                };
+
+                let values: Option<Vec<i64>> = solution.iter().map(|r| r.to_int()).collect();
+                let Some(values) = values else {
+                    // The system has a unique solution, so no other choice of stones can give an integer one.
+                    return Err(ThrowError::NonIntegerSolution(solution));
+                };
+
+                let rock = Rock { position: values[0..3].to_vec(), velocity: values[3..6].to_vec() };
+                if let Some(i) = (0..stones.len()).find(|i| collision_time(&rock, &stones[*i]).is_none()) {
+                    return Err(ThrowError::MissesStone(i));
+                }
+
+                return Ok(rock);
+            }
+        }
+    }
+
+    return Err(ThrowError::NoIndependentStones);
 }
 
 fn main() {
     let content = fs::read_to_string("./src/input24.txt").unwrap();
     let stones: Vec<_> = content.lines().map(parse_stone).collect();
 
-    let px = Variable { idx: 0, name: "px".to_owned() };
-    let py = Variable { idx: 1, name: "py".to_owned() };
-    let pz = Variable { idx: 2, name: "pz".to_owned() };
-    let vx = Variable { idx: 3, name: "vx".to_owned() };
-    let vy = Variable { idx: 4, name: "vy".to_owned() };
-    let vz = Variable { idx: 5, name: "vz".to_owned() };
-
-    // We can't represent these quantities using linear expressions of the variables, so we declare new variables.
-    // If we are lucky, we can still solve. (Spoiler: we can)
-    let pxvy = Variable { idx: 6, name: "pxvy".to_owned() };
-    let pxvz = Variable { idx: 7, name: "pxvz".to_owned() };
-    let pyvx = Variable { idx: 8, name: "pyvx".to_owned() };
-    let pyvz = Variable { idx: 9, name: "pyvz".to_owned() };
-    let pzvx = Variable { idx: 10, name: "pzvx".to_owned() };
-    let pzvy = Variable { idx: 11, name: "pzvy".to_owned() };
-
-    let mut system: Vec<Equation> = vec![];
-
-    for stone in stones {
-        let pxi = Rational::int(stone.position[0]);
-        let pyi = Rational::int(stone.position[1]);
-        let pzi = Rational::int(stone.position[2]);
-        let vxi = Rational::int(stone.velocity[0]);
-        let vyi = Rational::int(stone.velocity[1]);
-        let vzi = Rational::int(stone.velocity[2]);
-
-        system.push(make_equation(&px, &py, &vx,& vy, &pxvy, &pyvx, &pxi, &pyi, &vxi, &vyi));
-        system.push(make_equation(&py, &pz, &vy,& vz, &pyvz, &pzvy, &pyi, &pzi, &vyi, &vzi));
-        system.push(make_equation(&px, &pz, &vx,& vz, &pxvz, &pzvx, &pxi, &pzi, &vxi, &vzi));
-    }
-
-    let outcome = solve(&system);
-
-    match outcome {
-        // It is underconstrained due to the new variables, but the ones we need are defined.
-        Outcome::Underconstrained(solution) => {
-            let px_value = solution[px.idx].as_ref().unwrap().to_int().unwrap();
-            let py_value = solution[py.idx].as_ref().unwrap().to_int().unwrap();
-            let pz_value = solution[pz.idx].as_ref().unwrap().to_int().unwrap();
-            let sum = px_value + py_value + pz_value;
-
-            println!("px = {}, py = {}, pz = {}, sum = {}", px_value, py_value, pz_value, sum);
+    match find_rock(&stones) {
+        Ok(rock) => {
+            let sum: i64 = rock.position.iter().sum();
+            println!("px = {}, py = {}, pz = {}, sum = {}", rock.position[0], rock.position[1], rock.position[2], sum);
+            println!("vx = {}, vy = {}, vz = {}", rock.velocity[0], rock.velocity[1], rock.velocity[2]);
         }
-        _ => panic!()
+        Err(err) => println!("No throw hits every hailstone: {}", err)
     }
-}
\ No newline at end of file
+}
@@ -1,36 +1,30 @@
+use std::env;
 use std::fs;
-use std::collections::HashMap;
-use regex::Regex;
 
+mod named_graph;
+mod network;
+mod number_theory;
 
+use network::{Network, NodeMatcher, LEFT_RIGHT};
+
+// Usage: day8part1 [<start> <end> [<alphabet>]]
+// Nodes are picked with exact:NAME, suffix:SUFFIX or regex:PATTERN. The alphabet lists the instruction letters
+// in the order their neighbors are listed, LR by default.
 fn main() {
-    let content = fs::read_to_string("./src/input8.txt").unwrap();
-    let lines = content.lines();
-    let mut lines_iter = lines.into_iter();
-    let directions = lines_iter.next().unwrap();
-    _ = lines_iter.next();  // Blank line
+    let start = env::args().nth(1).unwrap_or("exact:AAA".to_owned());
+    let end = env::args().nth(2).unwrap_or("exact:ZZZ".to_owned());
+    let alphabet = env::args().nth(3).unwrap_or(LEFT_RIGHT.to_owned());
 
-    let mut network: HashMap<&str, (&str, &str)> = HashMap::new();
-    let line_regex = Regex::new(r"(.*) = \((.*), (.*)\)").unwrap();
-    for line in lines_iter {
-        let (_, [src, dst1, dst2]) = line_regex.captures(line).unwrap().extract();
-        network.insert(src, (dst1, dst2));
-    }
+    let content = fs::read_to_string("./src/input8.txt").unwrap();
+    let parsed = NodeMatcher::parse(&start).and_then(|start| {
+        let end = NodeMatcher::parse(&end)?;
+        let network = Network::parse(&content, &alphabet)?;
+        return Ok(network.ghost_steps(|name| start.matches(name), |name| end.matches(name)));
+    });
 
-    let mut loc: &str = "AAA";
-    let mut steps = 0;
-    let mut dir_i = 0;
-    while loc != "ZZZ" {
-        let direction = directions.as_bytes()[dir_i % directions.len()];
-        let options = network.get(loc).unwrap();
-        loc = match direction {
-            b'L' => options.0,
-            b'R' => options.1,
-            _ => panic!("Invalid direction")
-        };
-        dir_i += 1;
-        steps += 1;
+    match parsed {
+        Ok(Some(steps)) => println!("{}", steps),
+        Ok(None) => println!("Never reaches an end node"),
+        Err(err) => println!("Invalid network: {}", err),
     }
-
-    println!("{}", steps);
-}
\ No newline at end of file
+}
@@ -0,0 +1,468 @@
+// Turns a grid maze into a small weighted graph between its junctions, then finds the longest hike through it.
+//
+// Corridors (runs of open tiles with exactly two open neighbors) are contracted into single edges,
+// so the search only has to branch where the maze actually branches.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+pub type Grid<T> = Vec<Vec<T>>;
+
+type Point = (usize, usize);
+
+type Direction = (isize, isize);
+
+const DIRECTIONS: [Direction; 4] = [(0, 1), (0, -1), (1, 0), (-1, 0)];
+
+fn step_in_direction(point: Point, direction: Direction, width: usize, height: usize) -> Option<Point> {
+    let (x, y) = point;
+    let (dx, dy) = direction;
+
+    let new_x = x.checked_add_signed(dx)?;
+    let new_y = y.checked_add_signed(dy)?;
+    if new_x < width && new_y < height {
+        return Some((new_x, new_y));
+    }
+
+    return None;
+}
+
+fn is_open(grid: &Grid<u8>, point: Point) -> bool {
+    let (x, y) = point;
+    return grid[y][x] != b'#';
+}
+
+fn open_neighbors(grid: &Grid<u8>, point: Point) -> impl Iterator<Item=Point> + '_ {
+    let height = grid.len();
+    let width = grid[0].len();
+
+    return DIRECTIONS.iter()
+        .filter_map(move |d| step_in_direction(point, *d, width, height))
+        .filter(|p| is_open(grid, *p));
+}
+
+#[derive(Debug)]
+pub struct HalfEdge {
+    pub other_v: usize,
+    pub weight: usize,
+}
+
+// v_to_edges[v] holds the edges leaving v. A two-way corridor is stored once in each direction.
+#[derive(Debug)]
+pub struct Graph {
+    pub start_v: usize,
+    pub end_v: usize,
+    pub v_to_edges: Vec<Vec<HalfEdge>>,
+}
+
+fn is_junction(grid: &Grid<u8>, point: Point, start: Point, end: Point) -> bool {
+    return point == start || point == end || open_neighbors(grid, point).count() != 2;
+}
+
+fn char_to_direction(char: u8) -> Option<Direction> {
+    match char {
+        b'>' => Some((1, 0)),
+        b'<' => Some((-1, 0)),
+        b'v' => Some((0, 1)),
+        b'^' => Some((0, -1)),
+        _ => None
+    }
+}
+
+// A slope can only be left in the direction it points.
+fn slope_allows(grid: &Grid<u8>, from: Point, to: Point) -> bool {
+    let (x, y) = from;
+    return match char_to_direction(grid[y][x]) {
+        Some((dx, dy)) => step_in_direction(from, (dx, dy), grid[0].len(), grid.len()) == Some(to),
+        None => true
+    };
+}
+
+// Walks from a junction along the corridor starting at `first` until it reaches another junction.
+// Returns None if `follow_slopes` is set and a slope forbids walking the corridor in this direction.
+fn follow_corridor(grid: &Grid<u8>, point_to_v: &HashMap<Point, usize>, from: Point, first: Point, follow_slopes: bool) -> Option<(Point, usize)> {
+    let mut prev = from;
+    let mut current = first;
+    let mut length = 1;
+
+    if follow_slopes && !slope_allows(grid, prev, current) {
+        return None;
+    }
+
+    while !point_to_v.contains_key(&current) {
+        // Not a junction, so there is exactly one way forward.
+        let next = open_neighbors(grid, current).find(|p| *p != prev).unwrap();
+        if follow_slopes && !slope_allows(grid, current, next) {
+            return None;
+        }
+
+        prev = current;
+        current = next;
+        length += 1;
+    }
+
+    return Some((current, length));
+}
+
+// With `follow_slopes`, the slopes ^>v< can only be walked down, so corridors containing them become one-way edges.
+pub fn contract_corridors(grid: &Grid<u8>, start: Point, end: Point, follow_slopes: bool) -> Graph {
+    let height = grid.len();
+    let width = grid[0].len();
+
+    let mut point_to_v: HashMap<Point, usize> = HashMap::new();
+    let mut v_to_point: Vec<Point> = vec![];
+    for y in 0..height {
+        for x in 0..width {
+            if is_open(grid, (x, y)) && is_junction(grid, (x, y), start, end) {
+                point_to_v.insert((x, y), v_to_point.len());
+                v_to_point.push((x, y));
+            }
+        }
+    }
+
+    let mut v_to_edges: Vec<Vec<HalfEdge>> = (0..v_to_point.len()).map(|_| vec![]).collect();
+    for v in 0..v_to_point.len() {
+        let point = v_to_point[v];
+        for first in open_neighbors(grid, point) {
+            let Some((other_point, weight)) = follow_corridor(grid, &point_to_v, point, first, follow_slopes) else { continue };
+            let other_v = point_to_v[&other_point];
+
+            // A corridor that loops back to where it started can never be part of a simple path.
+            if other_v != v {
+                v_to_edges[v].push(HalfEdge { other_v: other_v, weight: weight });
+            }
+        }
+    }
+
+    return Graph {
+        start_v: point_to_v[&start],
+        end_v: point_to_v[&end],
+        v_to_edges: v_to_edges,
+    };
+}
+
+// Vertices visited so far, one bit per vertex.
+type VisitedSet = u128;
+
+const MAX_VERTICES: usize = VisitedSet::BITS as usize;
+
+fn bit(v: usize) -> VisitedSet {
+    return 1 << v;
+}
+
+// Vertices that can still be reached from v without going through anything already visited (including v itself).
+fn reachable(graph: &Graph, v: usize, visited: VisitedSet) -> VisitedSet {
+    let mut seen = bit(v);
+    let mut to_visit = vec![v];
+
+    while let Some(u) = to_visit.pop() {
+        for half_edge in graph.v_to_edges[u].iter() {
+            let b = bit(half_edge.other_v);
+            if (visited | seen) & b == 0 {
+                seen |= b;
+                to_visit.push(half_edge.other_v);
+            }
+        }
+    }
+
+    return seen;
+}
+
+struct Frame {
+    v: usize,
+    visited: VisitedSet,
+    reachable: VisitedSet,
+    next_edge: usize,
+    // Longest way from v to the end without revisiting anything in `visited`.
+    longest: Option<usize>,
+}
+
+// Returns the length of the longest path from start_v to end_v that never visits a vertex twice,
+// or None if there is no path at all.
+//
+// The rest of the hike only depends on where we are and which vertices we can still get to,
+// so results are memoised on (vertex, reachable set). Different histories often cut off the same part of the graph.
+pub fn longest_hike(graph: &Graph) -> Option<usize> {
+    assert!(graph.v_to_edges.len() <= MAX_VERTICES, "Graph has {} vertices, but at most {} are supported", graph.v_to_edges.len(), MAX_VERTICES);
+
+    let mut memo: HashMap<(usize, VisitedSet), Option<usize>> = HashMap::new();
+    let start_reachable = reachable(graph, graph.start_v, 0);
+    let mut stack = vec![Frame { v: graph.start_v, visited: bit(graph.start_v), reachable: start_reachable, next_edge: 0, longest: None }];
+
+    loop {
+        let frame = stack.last_mut().unwrap();
+
+        if frame.v == graph.end_v {
+            frame.longest = Some(0);
+        } else if frame.reachable & bit(graph.end_v) != 0 && frame.next_edge < graph.v_to_edges[frame.v].len() {
+            let half_edge = &graph.v_to_edges[frame.v][frame.next_edge];
+            frame.next_edge += 1;
+
+            if frame.visited & bit(half_edge.other_v) != 0 {
+                continue;
+            }
+
+            let visited = frame.visited | bit(half_edge.other_v);
+            let reachable = reachable(graph, half_edge.other_v, frame.visited);
+            match memo.get(&(half_edge.other_v, reachable)) {
+                Some(rest) => {
+                    if let Some(rest) = rest {
+                        frame.longest = frame.longest.max(Some(rest + half_edge.weight));
+                    }
+                }
+                None => {
+                    stack.push(Frame { v: half_edge.other_v, visited: visited, reachable: reachable, next_edge: 0, longest: None });
+                }
+            }
+            continue;
+        }
+
+        // Done with this vertex.
+        let frame = stack.pop().unwrap();
+        memo.insert((frame.v, frame.reachable), frame.longest);
+
+        match stack.last_mut() {
+            Some(parent) => {
+                if let Some(rest) = frame.longest {
+                    let weight = graph.v_to_edges[parent.v][parent.next_edge - 1].weight;
+                    parent.longest = parent.longest.max(Some(rest + weight));
+                }
+            }
+            None => return frame.longest,
+        }
+    }
+}
+
+// For each vertex, the heaviest edge that leads into it.
+fn max_weights_in(graph: &Graph) -> Vec<usize> {
+    let mut max_in = vec![0; graph.v_to_edges.len()];
+    for edges in graph.v_to_edges.iter() {
+        for half_edge in edges.iter() {
+            max_in[half_edge.other_v] = max_in[half_edge.other_v].max(half_edge.weight);
+        }
+    }
+
+    return max_in;
+}
+
+// Every vertex we still visit has to be entered by some edge, so the rest of the hike
+// can't be longer than the heaviest way into each vertex we can still reach.
+fn upper_bound(max_in: &[usize], v: usize, reachable: VisitedSet) -> usize {
+    return (0..max_in.len()).filter(|u| *u != v && reachable & bit(*u) != 0).map(|u| max_in[u]).sum();
+}
+
+// A partial hike handed to a worker thread.
+#[derive(Clone, Copy)]
+struct Task {
+    v: usize,
+    visited: VisitedSet,
+    length: usize,
+}
+
+// The longest hike found by any thread, plus one (zero means no hike has been found yet).
+struct SharedBest {
+    best_plus_one: AtomicUsize,
+}
+
+impl SharedBest {
+    fn get(&self) -> Option<usize> {
+        return self.best_plus_one.load(Ordering::Relaxed).checked_sub(1);
+    }
+
+    fn offer(&self, length: usize) {
+        self.best_plus_one.fetch_max(length + 1, Ordering::Relaxed);
+    }
+
+    fn can_beat(&self, length_bound: usize) -> bool {
+        return self.get().map_or(true, |best| length_bound > best);
+    }
+}
+
+struct BoundFrame {
+    v: usize,
+    visited: VisitedSet,
+    length: usize,
+    next_edge: usize,
+}
+
+fn branch_and_bound(graph: &Graph, max_in: &[usize], task: Task, best: &SharedBest) {
+    let mut stack = vec![BoundFrame { v: task.v, visited: task.visited, length: task.length, next_edge: 0 }];
+
+    while let Some(frame) = stack.last_mut() {
+        if frame.v == graph.end_v {
+            best.offer(frame.length);
+            stack.pop();
+            continue;
+        }
+
+        if frame.next_edge == 0 {
+            let reachable = reachable(graph, frame.v, frame.visited);
+            if reachable & bit(graph.end_v) == 0 || !best.can_beat(frame.length + upper_bound(max_in, frame.v, reachable)) {
+                stack.pop();
+                continue;
+            }
+        }
+
+        if frame.next_edge == graph.v_to_edges[frame.v].len() {
+            stack.pop();
+            continue;
+        }
+
+        let half_edge = &graph.v_to_edges[frame.v][frame.next_edge];
+        frame.next_edge += 1;
+
+        if frame.visited & bit(half_edge.other_v) == 0 {
+            let next = BoundFrame {
+                v: half_edge.other_v,
+                visited: frame.visited | bit(half_edge.other_v),
+                length: frame.length + half_edge.weight,
+                next_edge: 0
+            };
+            stack.push(next);
+        }
+    }
+}
+
+// Expands the first few levels of the search into independent partial hikes, so there's enough work to share out.
+fn split_into_tasks(graph: &Graph, min_tasks: usize, best: &SharedBest) -> Vec<Task> {
+    let mut tasks = vec![Task { v: graph.start_v, visited: bit(graph.start_v), length: 0 }];
+
+    while tasks.len() < min_tasks {
+        let mut next_tasks = vec![];
+        let mut progress = false;
+
+        for task in tasks {
+            if task.v == graph.end_v {
+                best.offer(task.length);
+                continue;
+            }
+
+            for half_edge in graph.v_to_edges[task.v].iter() {
+                if task.visited & bit(half_edge.other_v) == 0 {
+                    next_tasks.push(Task {
+                        v: half_edge.other_v,
+                        visited: task.visited | bit(half_edge.other_v),
+                        length: task.length + half_edge.weight
+                    });
+                    progress = true;
+                }
+            }
+        }
+
+        tasks = next_tasks;
+        if !progress {
+            break;
+        }
+    }
+
+    return tasks;
+}
+
+const TASKS_PER_THREAD: usize = 16;
+
+// Same answer as longest_hike, but prunes any branch that can't beat the best hike found so far,
+// and shares the branches out between threads.
+pub fn longest_hike_parallel(graph: &Graph) -> Option<usize> {
+    assert!(graph.v_to_edges.len() <= MAX_VERTICES, "Graph has {} vertices, but at most {} are supported", graph.v_to_edges.len(), MAX_VERTICES);
+
+    let num_threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let max_in = max_weights_in(graph);
+    let best = SharedBest { best_plus_one: AtomicUsize::new(0) };
+    let tasks = split_into_tasks(graph, num_threads * TASKS_PER_THREAD, &best);
+    let next_task = AtomicUsize::new(0);
+
+    thread::scope(|scope| {
+        for _ in 0..num_threads {
+            scope.spawn(|| {
+                loop {
+                    let i = next_task.fetch_add(1, Ordering::Relaxed);
+                    if i >= tasks.len() {
+                        break;
+                    }
+                    branch_and_bound(graph, &max_in, tasks[i], &best);
+                }
+            });
+        }
+    });
+
+    return best.get();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "#.#####################
+#.......#########...###
+#######.#########.#.###
+###.....#.>.>.###.#.###
+###v#####.#v#.###.#.###
+###.>...#.#.#.....#...#
+###v###.#.#.#########.#
+###...#.#.#.......#...#
+#####.#.#.#######.#.###
+#.....#.#.#.......#...#
+#.#####.#.#.#########v#
+#.#...#...#...###...>.#
+#.#.#v#######v###.###v#
+#...#.>.#...>.>.#.###.#
+#####v#.#.###v#.#.###.#
+#.....#...#...#.#.#...#
+#.#########.###.#.#.###
+#...###...#...#...#.###
+###.###.#.###v#####v###
+#...#...#.#.>.>.#.>.###
+#.###.###.#.###.#.#v###
+#.....###...###...#...#
+#####################.#";
+
+    fn example_grid() -> Grid<u8> {
+        return EXAMPLE.lines().map(|l| l.bytes().collect()).collect();
+    }
+
+    #[test]
+    fn test_contract_corridors() {
+        let graph = contract_corridors(&example_grid(), (1, 0), (21, 22), false);
+
+        assert_eq!(graph.v_to_edges.len(), 9);
+        assert_eq!(graph.v_to_edges[graph.start_v].len(), 1);
+        assert_eq!(graph.v_to_edges[graph.start_v][0].weight, 15);
+    }
+
+    #[test]
+    fn test_longest_hike() {
+        let graph = contract_corridors(&example_grid(), (1, 0), (21, 22), false);
+
+        assert_eq!(longest_hike(&graph), Some(154));
+        assert_eq!(longest_hike_parallel(&graph), Some(154));
+    }
+
+    #[test]
+    fn test_longest_hike_with_slopes() {
+        let graph = contract_corridors(&example_grid(), (1, 0), (21, 22), true);
+
+        assert_eq!(longest_hike(&graph), Some(94));
+        assert_eq!(longest_hike_parallel(&graph), Some(94));
+    }
+
+    #[test]
+    fn test_slopes_are_one_way() {
+        let grid: Grid<u8> = vec![b"#.#".to_vec(), b"#v#".to_vec(), b"#.#".to_vec()];
+
+        let down = contract_corridors(&grid, (1, 0), (1, 2), true);
+        assert_eq!(longest_hike(&down), Some(2));
+
+        let up = contract_corridors(&grid, (1, 2), (1, 0), true);
+        assert_eq!(longest_hike(&up), None);
+    }
+
+    #[test]
+    fn test_longest_hike_no_path() {
+        let grid: Grid<u8> = vec![b"#.#".to_vec(), b"###".to_vec(), b"#.#".to_vec()];
+        let graph = contract_corridors(&grid, (1, 0), (1, 2), false);
+
+        assert_eq!(longest_hike(&graph), None);
+        assert_eq!(longest_hike_parallel(&graph), None);
+    }
+}
@@ -0,0 +1,292 @@
+// Minimum cuts of unweighted, undirected graphs.
+//
+// Vertices are indices into `names`, and every algorithm reports the cut edges by name.
+// Stoer-Wagner and the max-flow cut are deterministic. Karger is kept around because it's fun.
+
+use std::collections::{BTreeMap, BinaryHeap, VecDeque};
+use std::mem;
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use crate::union_find::UnionFind;
+
+#[derive(Debug, PartialEq)]
+pub struct Cut<'a> {
+    // Sorted, with each pair's names sorted too, so the output doesn't depend on the algorithm.
+    pub edges: Vec<(&'a str, &'a str)>,
+    pub component_sizes: (usize, usize),
+}
+
+fn cut_from_side<'a>(names: &[&'a str], edges: &[(usize, usize)], in_side: &[bool]) -> Cut<'a> {
+    let mut cut_edges: Vec<(&str, &str)> = edges.iter()
+        .filter(|(a, b)| in_side[*a] != in_side[*b])
+        .map(|(a, b)| (names[*a].min(names[*b]), names[*a].max(names[*b])))
+        .collect();
+    cut_edges.sort();
+
+    let side_size = in_side.iter().filter(|x| **x).count();
+
+    return Cut { edges: cut_edges, component_sizes: (side_size, names.len() - side_size) };
+}
+
+// Stoer-Wagner: repeatedly find a maximum adjacency ordering, note the cut around the last vertex,
+// then merge the last two vertices. The smallest of those cuts is a global minimum cut.
+pub fn stoer_wagner<'a>(names: &[&'a str], edges: &[(usize, usize)]) -> Cut<'a> {
+    let n = names.len();
+
+    // BTreeMaps rather than HashMaps so ties are always broken the same way.
+    let mut adjacent: Vec<BTreeMap<usize, usize>> = (0..n).map(|_| BTreeMap::new()).collect();
+    for (a, b) in edges.iter() {
+        if a != b {
+            *adjacent[*a].entry(*b).or_insert(0) += 1;
+            *adjacent[*b].entry(*a).or_insert(0) += 1;
+        }
+    }
+
+    let mut members: Vec<Vec<usize>> = (0..n).map(|v| vec![v]).collect();
+    let mut merged = vec![false; n];
+    let mut best_weight = usize::MAX;
+    let mut best_side: Vec<usize> = vec![];
+
+    for remaining in (2..=n).rev() {
+        let start = (0..n).find(|v| !merged[*v]).unwrap();
+
+        let mut weights = vec![0; n];
+        let mut added = vec![false; n];
+        let mut heap = BinaryHeap::from([(0, start)]);
+        let mut prev = start;
+        let mut last = start;
+        let mut cut_of_phase = 0;
+
+        for _ in 0..remaining {
+            // Skip stale heap entries left behind when a weight went up.
+            let (weight, v) = loop {
+                match heap.pop() {
+                    Some((weight, v)) if !added[v] && weight == weights[v] => break (weight, v),
+                    Some(_) => continue,
+                    // A disconnected graph leaves vertices that never make it onto the heap.
+                    None => break (0, (0..n).find(|u| !merged[*u] && !added[*u]).unwrap()),
+                }
+            };
+
+            added[v] = true;
+            prev = last;
+            last = v;
+            cut_of_phase = weight;
+
+            for (u, w) in adjacent[v].iter() {
+                if !added[*u] {
+                    weights[*u] += w;
+                    heap.push((weights[*u], *u));
+                }
+            }
+        }
+
+        if cut_of_phase < best_weight {
+            best_weight = cut_of_phase;
+            best_side = members[last].clone();
+        }
+
+        // Merge last into prev.
+        let last_adjacent = mem::take(&mut adjacent[last]);
+        for (u, w) in last_adjacent {
+            adjacent[u].remove(&last);
+            if u != prev {
+                *adjacent[prev].entry(u).or_insert(0) += w;
+                *adjacent[u].entry(prev).or_insert(0) += w;
+            }
+        }
+        let last_members = mem::take(&mut members[last]);
+        members[prev].extend(last_members);
+        merged[last] = true;
+    }
+
+    let mut in_side = vec![false; n];
+    for v in best_side {
+        in_side[v] = true;
+    }
+
+    return cut_from_side(names, edges, &in_side);
+}
+
+struct Arc {
+    to: usize,
+    capacity: usize,
+    // Index of the arc going the other way, which gains capacity when this one is used.
+    reverse: usize,
+}
+
+// Edmonds-Karp: push flow along shortest augmenting paths until there are none left.
+// The vertices still reachable from s in the residual graph form the s side of a minimum s-t cut.
+pub fn min_st_cut<'a>(names: &[&'a str], edges: &[(usize, usize)], s: usize, t: usize) -> Cut<'a> {
+    assert_ne!(s, t);
+    let n = names.len();
+
+    let mut arcs: Vec<Arc> = vec![];
+    let mut v_to_arcs: Vec<Vec<usize>> = (0..n).map(|_| vec![]).collect();
+    for (a, b) in edges.iter() {
+        // An undirected edge is a pair of arcs that are each other's reverse.
+        let i = arcs.len();
+        arcs.push(Arc { to: *b, capacity: 1, reverse: i + 1 });
+        arcs.push(Arc { to: *a, capacity: 1, reverse: i });
+        v_to_arcs[*a].push(i);
+        v_to_arcs[*b].push(i + 1);
+    }
+
+    loop {
+        // Breadth first search for a path with spare capacity, remembering the arc used to reach each vertex.
+        let mut arc_to: Vec<Option<usize>> = vec![None; n];
+        let mut seen = vec![false; n];
+        let mut queue = VecDeque::from([s]);
+        seen[s] = true;
+
+        while let Some(v) = queue.pop_front() {
+            for arc_i in v_to_arcs[v].iter() {
+                let arc = &arcs[*arc_i];
+                if arc.capacity > 0 && !seen[arc.to] {
+                    seen[arc.to] = true;
+                    arc_to[arc.to] = Some(*arc_i);
+                    queue.push_back(arc.to);
+                }
+            }
+        }
+
+        if !seen[t] {
+            return cut_from_side(names, edges, &seen);
+        }
+
+        // Every arc has capacity 1, so each augmenting path carries exactly one unit.
+        let mut v = t;
+        while let Some(arc_i) = arc_to[v] {
+            let reverse = arcs[arc_i].reverse;
+            arcs[arc_i].capacity -= 1;
+            arcs[reverse].capacity += 1;
+            v = arcs[reverse].to;
+        }
+    }
+}
+
+// Global minimum cut from s-t cuts: vertex 0 is on one side, so try every other vertex on the other.
+// With fewer than two vertices there's nothing to cut, and everything ends up on the same side.
+pub fn min_cut_by_flow<'a>(names: &[&'a str], edges: &[(usize, usize)]) -> Cut<'a> {
+    return (1..names.len())
+        .map(|t| min_st_cut(names, edges, 0, t))
+        .min_by_key(|cut| cut.edges.len())
+        .unwrap_or_else(|| cut_from_side(names, edges, &vec![false; names.len()]));
+}
+
+fn randomized_cut<R: Rng>(edges: &[(usize, usize)], n: usize, rng: &mut R) -> Vec<bool> {
+    // Thanks 15-251!
+
+    let mut edges = edges.to_vec();
+    edges.shuffle(rng);
+
+    let mut union_find = UnionFind::new(n);
+    let mut edge_idx = 0;
+
+    while union_find.num_components() > 2 && edge_idx < edges.len() {
+        let (a, b) = edges[edge_idx];
+        union_find.union(a, b);
+        edge_idx += 1;
+    }
+
+    let side = union_find.find(0);
+    return (0..n).map(|v| union_find.find(v) == side).collect();
+}
+
+// Karger: contract random edges until two vertices are left. Each try only finds a minimum cut with some probability,
+// so this keeps the smallest cut over at most `max_iterations` tries, stopping early once one has at most `good_enough` edges.
+// Pass a seeded rng to get the same answer every run.
+pub fn karger<'a, R: Rng>(names: &[&'a str], edges: &[(usize, usize)], rng: &mut R, max_iterations: usize, good_enough: usize) -> Option<Cut<'a>> {
+    let mut best: Option<Cut> = None;
+
+    for _ in 0..max_iterations {
+        let cut = cut_from_side(names, edges, &randomized_cut(edges, names.len(), rng));
+        let done = cut.edges.len() <= good_enough;
+
+        if best.as_ref().map_or(true, |b| cut.edges.len() < b.edges.len()) {
+            best = Some(cut);
+        }
+
+        if done {
+            break;
+        }
+    }
+
+    return best;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::named_graph::NamedGraph;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    const EXAMPLE: &str = "jqt: rhn xhk nvd
+rsh: frs pzl lsr
+xhk: hfx
+cmg: qnr nvd lhk bvb
+rhn: xhk bvb hfx
+bvb: xhk hfx
+pzl: lsr hfx nvd
+qnr: nvd
+ntq: jqt hfx bvb xhk
+nvd: lhk
+lsr: lhk
+rzs: qnr cmg lsr rsh
+frs: qnr lhk lsr";
+
+    fn assert_example_cut(cut: Cut) {
+        assert_eq!(cut.edges, vec![("bvb", "cmg"), ("hfx", "pzl"), ("jqt", "nvd")]);
+        assert_eq!(cut.component_sizes.0 * cut.component_sizes.1, 54);
+    }
+
+    #[test]
+    fn test_stoer_wagner() {
+        let graph = NamedGraph::from_colon_lines(EXAMPLE, false);
+        assert_example_cut(stoer_wagner(graph.names(), graph.edges()));
+    }
+
+    #[test]
+    fn test_min_cut_by_flow() {
+        let graph = NamedGraph::from_colon_lines(EXAMPLE, false);
+        assert_example_cut(min_cut_by_flow(graph.names(), graph.edges()));
+    }
+
+    #[test]
+    fn test_disconnected() {
+        // d isn't connected to anything, so it can be cut off for free.
+        let names = ["a", "b", "c", "d"];
+        let edges = [(0, 1), (0, 2), (1, 2)];
+
+        for cut in [stoer_wagner(&names, &edges), min_cut_by_flow(&names, &edges)] {
+            assert_eq!(cut.edges, vec![]);
+            assert_eq!(cut.component_sizes.0 * cut.component_sizes.1, 3);
+        }
+    }
+
+    #[test]
+    fn test_single_vertex() {
+        for cut in [stoer_wagner(&["a"], &[]), min_cut_by_flow(&["a"], &[])] {
+            assert_eq!(cut.edges, vec![]);
+            assert_eq!(cut.component_sizes, (0, 1));
+        }
+    }
+
+    #[test]
+    fn test_min_st_cut() {
+        let graph = NamedGraph::from_colon_lines(EXAMPLE, false);
+        let s = graph.idx("jqt").unwrap();
+        let t = graph.idx("cmg").unwrap();
+
+        assert_example_cut(min_st_cut(graph.names(), graph.edges(), s, t));
+    }
+
+    #[test]
+    fn test_karger() {
+        let graph = NamedGraph::from_colon_lines(EXAMPLE, false);
+        let mut rng = StdRng::seed_from_u64(25);
+        assert_example_cut(karger(graph.names(), graph.edges(), &mut rng, 1000, 3).unwrap());
+    }
+}
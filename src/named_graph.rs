@@ -0,0 +1,148 @@
+// A graph whose vertices are named by strings in the input, interned to dense indices 0..n
+// so the algorithms can work with plain Vecs.
+
+use std::collections::HashMap;
+
+pub struct NamedGraph<'a> {
+    directed: bool,
+    names: Vec<&'a str>,
+    name_to_idx: HashMap<&'a str, usize>,
+    // Each edge once, in the order it was added.
+    edges: Vec<(usize, usize)>,
+    // For an undirected graph, outgoing and incoming are the same.
+    outgoing: Vec<Vec<usize>>,
+    incoming: Vec<Vec<usize>>,
+}
+
+impl<'a> NamedGraph<'a> {
+    pub fn new(directed: bool) -> NamedGraph<'a> {
+        return NamedGraph {
+            directed: directed,
+            names: vec![],
+            name_to_idx: HashMap::new(),
+            edges: vec![],
+            outgoing: vec![],
+            incoming: vec![],
+        };
+    }
+
+    // Returns the index for `name`, assigning the next free one if it hasn't been seen before.
+    pub fn intern(&mut self, name: &'a str) -> usize {
+        if let Some(idx) = self.name_to_idx.get(name) {
+            return *idx;
+        }
+
+        let idx = self.names.len();
+        self.names.push(name);
+        self.name_to_idx.insert(name, idx);
+        self.outgoing.push(vec![]);
+        self.incoming.push(vec![]);
+        return idx;
+    }
+
+    pub fn add_edge(&mut self, src: &'a str, dst: &'a str) {
+        let src_idx = self.intern(src);
+        let dst_idx = self.intern(dst);
+
+        self.edges.push((src_idx, dst_idx));
+        self.outgoing[src_idx].push(dst_idx);
+        self.incoming[dst_idx].push(src_idx);
+
+        if !self.directed {
+            self.outgoing[dst_idx].push(src_idx);
+            self.incoming[src_idx].push(dst_idx);
+        }
+    }
+
+    pub fn is_directed(&self) -> bool {
+        return self.directed;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.names.len();
+    }
+
+    pub fn idx(&self, name: &str) -> Option<usize> {
+        return self.name_to_idx.get(name).copied();
+    }
+
+    pub fn name(&self, idx: usize) -> &'a str {
+        return self.names[idx];
+    }
+
+    pub fn names(&self) -> &[&'a str] {
+        return &self.names;
+    }
+
+    pub fn edges(&self) -> &[(usize, usize)] {
+        return &self.edges;
+    }
+
+    // Neighbors are listed in the order their edges were added.
+    pub fn outgoing(&self, idx: usize) -> &[usize] {
+        return &self.outgoing[idx];
+    }
+
+    pub fn incoming(&self, idx: usize) -> &[usize] {
+        return &self.incoming[idx];
+    }
+
+    // Lines like "jqt: rhn xhk nvd".
+    pub fn from_colon_lines(content: &'a str, directed: bool) -> NamedGraph<'a> {
+        let mut graph = NamedGraph::new(directed);
+
+        for line in content.lines() {
+            let (src, dsts) = line.split_once(": ").unwrap();
+            graph.intern(src);
+            for dst in dsts.split(' ') {
+                graph.add_edge(src, dst);
+            }
+        }
+
+        return graph;
+    }
+
+    // Lines like "%a -> b, c". Any of the `sigils` at the start of the source name are dropped,
+    // so "%a" and "a" are the same vertex. Always directed.
+    pub fn from_arrow_lines(content: &'a str, sigils: &[char]) -> NamedGraph<'a> {
+        let mut graph = NamedGraph::new(true);
+
+        for line in content.lines() {
+            let (src, dsts) = line.split_once(" -> ").unwrap();
+            let src = src.trim_start_matches(sigils);
+            graph.intern(src);
+            for dst in dsts.split(", ") {
+                graph.add_edge(src, dst);
+            }
+        }
+
+        return graph;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_colon_lines() {
+        let graph = NamedGraph::from_colon_lines("a: b c\nc: d", false);
+
+        assert_eq!(graph.names(), &["a", "b", "c", "d"]);
+        assert_eq!(graph.edges(), &[(0, 1), (0, 2), (2, 3)]);
+        assert_eq!(graph.outgoing(graph.idx("c").unwrap()), &[0, 3]);
+        assert_eq!(graph.idx("e"), None);
+    }
+
+    #[test]
+    fn test_from_arrow_lines() {
+        let graph = NamedGraph::from_arrow_lines("broadcaster -> a, b\n%a -> con\n&con -> output", &['%', '&']);
+
+        assert!(graph.is_directed());
+        assert_eq!(graph.len(), 5);
+        let con = graph.idx("con").unwrap();
+        assert_eq!(graph.incoming(con), &[graph.idx("a").unwrap()]);
+        assert_eq!(graph.name(graph.outgoing(con)[0]), "output");
+        assert_eq!(graph.outgoing(graph.idx("output").unwrap()), &[] as &[usize]);
+    }
+}
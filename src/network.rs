@@ -0,0 +1,352 @@
+// The haunted wasteland's network: every node has a neighbor for each letter of an instruction alphabet (L and R
+// in the puzzle), and walks follow a list of instructions, starting over from the first when they run out.
+
+use std::collections::HashSet;
+use std::fmt;
+use regex::Regex;
+
+use crate::named_graph::NamedGraph;
+use crate::number_theory::{combine_residue_sets, first_at_least};
+
+pub const LEFT_RIGHT: &str = "LR";
+
+pub struct Network<'a> {
+    // Each instruction as an index into the alphabet.
+    pub instructions: Vec<usize>,
+    // Each node's outgoing edges are its neighbors in alphabet order.
+    pub graph: NamedGraph<'a>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum NetworkError {
+    RepeatedLetter { letter: char, alphabet: String },
+    UnknownInstruction { instruction: char, alphabet: String },
+    NoInstructions,
+    BadLine { line: usize },
+    WrongNumberOfNeighbors { line: usize, expected: usize, found: usize },
+    UndefinedNode { name: String },
+    DuplicateNode { line: usize, name: String },
+    BadMatcher { spec: String },
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NetworkError::RepeatedLetter { letter, alphabet } => write!(f, "'{}' is in the alphabet '{}' more than once", letter, alphabet),
+            NetworkError::UnknownInstruction { instruction, alphabet } => write!(f, "instruction '{}' is not in the alphabet '{}'", instruction, alphabet),
+            NetworkError::NoInstructions => write!(f, "the first line should be the instructions, but it's empty"),
+            NetworkError::BadLine { line } => write!(f, "line {}: expected NODE = (NEIGHBOR, ...)", line),
+            NetworkError::WrongNumberOfNeighbors { line, expected, found } => write!(f, "line {}: expected {} neighbors, one per instruction letter, got {}", line, expected, found),
+            NetworkError::UndefinedNode { name } => write!(f, "node {} is a neighbor but has no line of its own", name),
+            NetworkError::DuplicateNode { line, name } => write!(f, "line {}: node {} already has a line of its own", line, name),
+            NetworkError::BadMatcher { spec } => write!(f, "expected exact:NAME, suffix:SUFFIX or regex:PATTERN, got '{}'", spec),
+        }
+    }
+}
+
+// Picks out start or end nodes by name.
+pub enum NodeMatcher {
+    Exact(String),
+    Suffix(String),
+    Pattern(Regex),
+}
+
+impl NodeMatcher {
+    // "exact:AAA", "suffix:Z" or "regex:^1.A$".
+    pub fn parse(spec: &str) -> Result<NodeMatcher, NetworkError> {
+        let bad_matcher = || NetworkError::BadMatcher { spec: spec.to_owned() };
+
+        return match spec.split_once(':').ok_or_else(bad_matcher)? {
+            ("exact", name) => Ok(NodeMatcher::Exact(name.to_owned())),
+            ("suffix", suffix) => Ok(NodeMatcher::Suffix(suffix.to_owned())),
+            ("regex", pattern) => Regex::new(pattern).map(NodeMatcher::Pattern).map_err(|_| bad_matcher()),
+            _ => Err(bad_matcher()),
+        };
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        return match self {
+            NodeMatcher::Exact(exact) => name == exact,
+            NodeMatcher::Suffix(suffix) => name.ends_with(suffix.as_str()),
+            NodeMatcher::Pattern(pattern) => pattern.is_match(name),
+        };
+    }
+}
+
+// Where a walk from one start node lands on end nodes, forever.
+//
+// The walk's state is its node plus where it is in the instructions. There are only so many states, so the walk
+// eventually gets back to one it has seen and goes round the same cycle from then on.
+#[derive(Debug, PartialEq)]
+pub struct GhostPath {
+    // Steps before the cycle starts that end on an end node.
+    pub prefix_hits: Vec<u64>,
+    pub cycle_start: u64,
+    pub cycle_length: u64,
+    // Steps in cycle_start..cycle_start + cycle_length that end on an end node. Adding any multiple of
+    // cycle_length to one of these gives another.
+    pub cycle_hits: Vec<u64>,
+}
+
+impl<'a> Network<'a> {
+    // The instructions, a blank line, then lines like "AAA = (BBB, CCC)", with a neighbor for each letter of `alphabet`.
+    pub fn parse(content: &'a str, alphabet: &str) -> Result<Network<'a>, NetworkError> {
+        let letters: Vec<char> = alphabet.chars().collect();
+        // Each letter picks out a neighbor by its position, so they all have to be different.
+        if let Some(i) = (0..letters.len()).find(|i| letters[..*i].contains(&letters[*i])) {
+            return Err(NetworkError::RepeatedLetter { letter: letters[i], alphabet: alphabet.to_owned() });
+        }
+
+        let mut lines = content.lines();
+        let instructions = lines.next().unwrap_or("").chars()
+            .map(|instruction| letters.iter().position(|letter| *letter == instruction).ok_or_else(|| NetworkError::UnknownInstruction { instruction: instruction, alphabet: alphabet.to_owned() }))
+            .collect::<Result<Vec<usize>, NetworkError>>()?;
+        if instructions.is_empty() {
+            return Err(NetworkError::NoInstructions);
+        }
+        if lines.next().map_or(false, |line| !line.is_empty()) {
+            return Err(NetworkError::BadLine { line: 2 });
+        }
+
+        let num_neighbors = letters.len();
+        let mut graph = NamedGraph::new(true);
+        let mut defined = HashSet::new();
+        let line_regex = Regex::new(r"^(\w+) = \((.*)\)$").unwrap();
+        for (i, line) in lines.enumerate() {
+            let line_number = i + 3;
+            let (_, [src, neighbors]) = line_regex.captures(line).ok_or(NetworkError::BadLine { line: line_number })?.extract();
+
+            let neighbors: Vec<&str> = neighbors.split(", ").collect();
+            if neighbors.len() != num_neighbors {
+                return Err(NetworkError::WrongNumberOfNeighbors { line: line_number, expected: num_neighbors, found: neighbors.len() });
+            }
+
+            if graph.idx(src).map_or(false, |node| defined.contains(&node)) {
+                return Err(NetworkError::DuplicateNode { line: line_number, name: src.to_owned() });
+            }
+            defined.insert(graph.intern(src));
+            for neighbor in neighbors {
+                graph.add_edge(src, neighbor);
+            }
+        }
+
+        // Anything that was only ever a neighbor has no way out.
+        if let Some(undefined) = (0..graph.len()).find(|node| !defined.contains(node)) {
+            return Err(NetworkError::UndefinedNode { name: graph.name(undefined).to_owned() });
+        }
+
+        return Ok(Network { instructions: instructions, graph: graph });
+    }
+
+    // The node you get to from `node` on step number `step` (counting from 0).
+    pub fn next(&self, node: usize, step: u64) -> usize {
+        return self.graph.outgoing(node)[self.instructions[(step % self.instructions.len() as u64) as usize]];
+    }
+
+    pub fn ghost_path(&self, start: usize, is_end: impl Fn(&str) -> bool) -> GhostPath {
+        let num_instructions = self.instructions.len();
+
+        // The step each (node, instruction index) state was first reached on.
+        let mut first_seen: Vec<Option<u64>> = vec![None; self.graph.len() * num_instructions];
+        let mut hits = vec![];
+        let mut node = start;
+        let mut step: u64 = 0;
+
+        loop {
+            let state = node * num_instructions + (step % num_instructions as u64) as usize;
+            if let Some(cycle_start) = first_seen[state] {
+                let (prefix_hits, cycle_hits) = hits.into_iter().partition(|hit| *hit < cycle_start);
+                return GhostPath {
+                    prefix_hits: prefix_hits,
+                    cycle_start: cycle_start,
+                    cycle_length: step - cycle_start,
+                    cycle_hits: cycle_hits,
+                };
+            }
+            first_seen[state] = Some(step);
+
+            if is_end(self.graph.name(node)) {
+                hits.push(step);
+            }
+
+            node = self.next(node, step);
+            step += 1;
+        }
+    }
+
+    // The first step on which walks from every start node are all on end nodes at once, if there is one.
+    pub fn ghost_steps(&self, is_start: impl Fn(&str) -> bool, is_end: impl Fn(&str) -> bool) -> Option<u64> {
+        let starts: Vec<usize> = (0..self.graph.len()).filter(|node| is_start(self.graph.name(*node))).collect();
+        let paths: Vec<GhostPath> = starts.iter().map(|start| self.ghost_path(*start, &is_end)).collect();
+
+        // Until every walk is in its cycle, just walk them all together.
+        let all_cycling = paths.iter().map(|path| path.cycle_start).max().unwrap_or(0);
+        let mut nodes = starts.clone();
+        for step in 0..all_cycling {
+            if nodes.iter().all(|node| is_end(self.graph.name(*node))) {
+                return Some(step);
+            }
+            nodes = nodes.iter().map(|node| self.next(*node, step)).collect();
+        }
+
+        // After that, each walk is on an end node exactly when the step is one of its cycle hits mod its cycle length.
+        let mut residues: Vec<i128> = vec![0];
+        let mut modulus: i128 = 1;
+        for path in paths.iter() {
+            let cycle_hits: Vec<i128> = path.cycle_hits.iter().map(|hit| *hit as i128).collect();
+            (residues, modulus) = combine_residue_sets(&residues, modulus, &cycle_hits, path.cycle_length as i128);
+        }
+
+        return first_at_least(&residues, modulus, all_cycling as i128).map(|step| step as u64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    const EXAMPLE: &str = "LR
+
+11A = (11B, XXX)
+11B = (XXX, 11Z)
+11Z = (11B, XXX)
+22A = (22B, XXX)
+22B = (22C, 22C)
+22C = (22Z, 22Z)
+22Z = (22B, 22B)
+XXX = (XXX, XXX)";
+
+    fn brute_force(network: &Network, is_start: impl Fn(&str) -> bool, is_end: impl Fn(&str) -> bool, max_steps: u64) -> Option<u64> {
+        let mut nodes: Vec<usize> = (0..network.graph.len()).filter(|node| is_start(network.graph.name(*node))).collect();
+        for step in 0..=max_steps {
+            if nodes.iter().all(|node| is_end(network.graph.name(*node))) {
+                return Some(step);
+            }
+            nodes = nodes.iter().map(|node| network.next(*node, step)).collect();
+        }
+        return None;
+    }
+
+    #[test]
+    fn test_ghost_path() {
+        let network = Network::parse(EXAMPLE, LEFT_RIGHT).unwrap();
+        let path = network.ghost_path(network.graph.idx("22A").unwrap(), |name| name.ends_with('Z'));
+
+        assert_eq!(path, GhostPath { prefix_hits: vec![], cycle_start: 1, cycle_length: 6, cycle_hits: vec![3, 6] });
+    }
+
+    #[test]
+    fn test_ghost_steps() {
+        let network = Network::parse(EXAMPLE, LEFT_RIGHT).unwrap();
+        assert_eq!(network.ghost_steps(|name| name.ends_with('A'), |name| name.ends_with('Z')), Some(6));
+    }
+
+    #[test]
+    fn test_ghost_steps_unaligned() {
+        // 1A hits at 2 mod 3, 2A at odd steps from 3 on, and 3A at 0 or 2 mod 5 from 2 on.
+        let network = Network::parse("L
+
+1A = (1B, 1B)
+1B = (1Z, 1Z)
+1Z = (1A, 1A)
+2A = (2B, 2B)
+2B = (2C, 2C)
+2C = (2Z, 2Z)
+2Z = (2C, 2C)
+3A = (3B, 3B)
+3B = (3Z, 3Z)
+3Z = (3C, 3C)
+3C = (3D, 3D)
+3D = (3WZ, 3WZ)
+3WZ = (3B, 3B)", LEFT_RIGHT).unwrap();
+
+        assert_eq!(network.ghost_steps(|name| name.ends_with('A'), |name| name.ends_with('Z')), Some(5));
+        assert_eq!(network.ghost_steps(|name| name == "1A" || name == "2A", |name| name.ends_with('Z')), Some(5));
+        assert_eq!(network.ghost_steps(|name| name == "2A", |name| name == "1Z"), None);
+    }
+
+    #[test]
+    fn test_ghost_steps_random() {
+        let mut rng = StdRng::seed_from_u64(8);
+
+        for _ in 0..200 {
+            let num_nodes = rng.gen_range(2..12);
+            let instructions: String = (0..rng.gen_range(1..5)).map(|_| if rng.gen() { 'L' } else { 'R' }).collect();
+            let mut content = format!("{}\n\n", instructions);
+            for i in 0..num_nodes {
+                let suffix = |i: usize| ["A", "Z", "Q"][i % 3];
+                let left = rng.gen_range(0..num_nodes);
+                let right = rng.gen_range(0..num_nodes);
+                content += &format!("{}{} = ({}{}, {}{})\n", i, suffix(i), left, suffix(left), right, suffix(right));
+            }
+
+            let network = Network::parse(&content, LEFT_RIGHT).unwrap();
+            let expected = brute_force(&network, |name| name.ends_with('A'), |name| name.ends_with('Z'), 100000);
+            assert_eq!(network.ghost_steps(|name| name.ends_with('A'), |name| name.ends_with('Z')), expected, "{}", content);
+        }
+    }
+
+    #[test]
+    fn test_node_matcher() {
+        assert!(NodeMatcher::parse("exact:AAA").unwrap().matches("AAA"));
+        assert!(!NodeMatcher::parse("exact:AAA").unwrap().matches("BAAA"));
+        assert!(NodeMatcher::parse("suffix:Z").unwrap().matches("11Z"));
+        assert!(NodeMatcher::parse("regex:^2.[AB]$").unwrap().matches("22B"));
+        assert!(!NodeMatcher::parse("regex:^2.[AB]$").unwrap().matches("11B"));
+
+        assert!(NodeMatcher::parse("prefix:A").is_err());
+        assert!(NodeMatcher::parse("AAA").is_err());
+        assert!(NodeMatcher::parse("regex:(").is_err());
+    }
+
+    #[test]
+    fn test_three_way_network() {
+        // Instructions pick the first, second or third neighbor.
+        let network = Network::parse("021
+
+AAA = (BBB, CCC, AAA)
+BBB = (AAA, ZZZ, BBB)
+CCC = (ZZZ, CCC, BBB)
+ZZZ = (ZZZ, ZZZ, ZZZ)", "012").unwrap();
+
+        // AAA, then the first neighbor BBB, then the third BBB, then the second ZZZ.
+        let start = NodeMatcher::parse("exact:AAA").unwrap();
+        let end = NodeMatcher::parse("exact:ZZZ").unwrap();
+        assert_eq!(network.ghost_steps(|name| start.matches(name), |name| end.matches(name)), Some(3));
+    }
+
+    #[test]
+    fn test_unicode_alphabet() {
+        // Letters are counted as chars, not bytes.
+        let network = Network::parse("→←→
+
+AAA = (BBB, AAA)
+BBB = (AAA, ZZZ)
+ZZZ = (ZZZ, ZZZ)", "←→").unwrap();
+
+        assert_eq!(network.instructions, vec![1, 0, 1]);
+        assert_eq!(network.ghost_steps(|name| name == "AAA", |name| name == "ZZZ"), Some(3));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Network::parse("LR\n\nAAA = (AAA, AAA, AAA)", "LRL").err(),
+            Some(NetworkError::RepeatedLetter { letter: 'L', alphabet: "LRL".to_owned() }));
+        assert_eq!(Network::parse("LRX\n\nAAA = (AAA, AAA)", LEFT_RIGHT).err(),
+            Some(NetworkError::UnknownInstruction { instruction: 'X', alphabet: "LR".to_owned() }));
+        assert_eq!(Network::parse("\n\nAAA = (AAA, AAA)", LEFT_RIGHT).err(), Some(NetworkError::NoInstructions));
+        assert_eq!(Network::parse("", LEFT_RIGHT).err(), Some(NetworkError::NoInstructions));
+        assert_eq!(Network::parse("LR\n\nAAA = (AAA, AAA)\nBBB = AAA", LEFT_RIGHT).err(),
+            Some(NetworkError::BadLine { line: 4 }));
+        assert_eq!(Network::parse("LR\n\nAAA = (AAA, AAA, AAA)", LEFT_RIGHT).err(),
+            Some(NetworkError::WrongNumberOfNeighbors { line: 3, expected: 2, found: 3 }));
+        assert_eq!(Network::parse("LR\n\nAAA = (AAA, BBB)", LEFT_RIGHT).err(),
+            Some(NetworkError::UndefinedNode { name: "BBB".to_owned() }));
+        assert_eq!(Network::parse("LR\nAAA = (AAA, AAA)", LEFT_RIGHT).err(),
+            Some(NetworkError::BadLine { line: 2 }));
+        assert_eq!(Network::parse("LR\n\nAAA = (AAA, BBB)\nBBB = (AAA, AAA)\nAAA = (BBB, BBB)", LEFT_RIGHT).err(),
+            Some(NetworkError::DuplicateNode { line: 5, name: "AAA".to_owned() }));
+    }
+}
@@ -0,0 +1,137 @@
+// Modular arithmetic for combining periodic events: extended GCD, modular inverses and the Chinese remainder theorem.
+//
+// Everything is generic over num's Integer + Signed, so the same code works on i128 and BigInt.
+// Moduli are always positive, and residues come back in 0..modulus.
+
+use num::{Integer, Signed};
+
+// Returns (g, x, y) with a * x + b * y = g = gcd(a, b), and g >= 0.
+pub fn extended_gcd<T: Integer + Signed + Clone>(a: T, b: T) -> (T, T, T) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (T::one(), T::zero());
+    let (mut old_y, mut y) = (T::zero(), T::one());
+
+    while !r.is_zero() {
+        let q = old_r.clone() / r.clone();
+        (old_r, r) = (r.clone(), old_r - q.clone() * r);
+        (old_x, x) = (x.clone(), old_x - q.clone() * x);
+        (old_y, y) = (y.clone(), old_y - q * y);
+    }
+
+    if old_r.is_negative() {
+        return (-old_r, -old_x, -old_y);
+    }
+    return (old_r, old_x, old_y);
+}
+
+// The x with a * x = 1 mod m, if a and m are coprime.
+pub fn mod_inverse<T: Integer + Signed + Clone>(a: T, m: T) -> Option<T> {
+    let (g, x, _) = extended_gcd(a, m.clone());
+    if !g.is_one() {
+        return None;
+    }
+    return Some(x.mod_floor(&m));
+}
+
+// The x with x = a mod m and x = b mod n, as (x mod lcm(m, n), lcm(m, n)).
+// m and n don't have to be coprime, but then there might not be one.
+pub fn crt<T: Integer + Signed + Clone>(a: T, m: T, b: T, n: T) -> Option<(T, T)> {
+    let (g, p, _) = extended_gcd(m.clone(), n.clone());
+    let difference = b - a.clone();
+    if !(difference.clone() % g.clone()).is_zero() {
+        return None;
+    }
+
+    // m * p = g mod n, so adding m * p * (b - a) / g to a fixes up the second congruence without breaking the first.
+    let n_over_g = n / g.clone();
+    let lcm = m.clone() * n_over_g.clone();
+    let k = (difference / g * p).mod_floor(&n_over_g);
+    return Some(((a + m * k).mod_floor(&lcm), lcm));
+}
+
+// CRT over any number of (residue, modulus) pairs. An empty list is satisfied by everything, which is (0, 1).
+pub fn crt_all<T: Integer + Signed + Clone>(congruences: &[(T, T)]) -> Option<(T, T)> {
+    let mut result = (T::zero(), T::one());
+    for (a, m) in congruences.iter() {
+        result = crt(result.0, result.1, a.clone(), m.clone())?;
+    }
+    return Some(result);
+}
+
+// Everything that's one of `a_residues` mod m and also one of `b_residues` mod n, as sorted residues mod lcm(m, n).
+// Comes back empty if no pair is consistent.
+pub fn combine_residue_sets<T: Integer + Signed + Clone>(a_residues: &[T], m: T, b_residues: &[T], n: T) -> (Vec<T>, T) {
+    let lcm = m.lcm(&n);
+
+    let mut combined = vec![];
+    for a in a_residues.iter() {
+        for b in b_residues.iter() {
+            if let Some((x, _)) = crt(a.clone(), m.clone(), b.clone(), n.clone()) {
+                combined.push(x);
+            }
+        }
+    }
+    combined.sort();
+    combined.dedup();
+
+    return (combined, lcm);
+}
+
+// The smallest x >= min that's one of `residues` mod `modulus`.
+pub fn first_at_least<T: Integer + Signed + Clone>(residues: &[T], modulus: T, min: T) -> Option<T> {
+    return residues.iter().map(|r| min.clone() + (r.clone() - min.clone()).mod_floor(&modulus)).min();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num::BigInt;
+
+    #[test]
+    fn test_extended_gcd() {
+        assert_eq!(extended_gcd(240_i128, 46), (2, -9, 47));
+        assert_eq!(extended_gcd(-4_i128, 6).0, 2);
+        assert_eq!(extended_gcd(7_i128, 0), (7, 1, 0));
+    }
+
+    #[test]
+    fn test_mod_inverse() {
+        assert_eq!(mod_inverse(3_i128, 11), Some(4));
+        assert_eq!(mod_inverse(-3_i128, 11), Some(7));
+        assert_eq!(mod_inverse(4_i128, 6), None);
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(crt(2_i128, 3, 3, 5), Some((8, 15)));
+        // Not coprime, but consistent.
+        assert_eq!(crt(2_i128, 4, 4, 6), Some((10, 12)));
+        assert_eq!(crt(1_i128, 4, 2, 6), None);
+        assert_eq!(crt_all(&[(2_i128, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        assert_eq!(crt_all::<i128>(&[]), Some((0, 1)));
+    }
+
+    #[test]
+    fn test_crt_big() {
+        // Two primes whose product doesn't fit in an i128.
+        let p = BigInt::from(2_i128.pow(89) - 1);
+        let q = BigInt::from(2_i128.pow(107) - 1);
+        let (x, lcm) = crt(BigInt::from(5), p.clone(), BigInt::from(7), q.clone()).unwrap();
+
+        assert_eq!(lcm, p.clone() * q.clone());
+        assert_eq!(x.mod_floor(&p), BigInt::from(5));
+        assert_eq!(x.mod_floor(&q), BigInt::from(7));
+    }
+
+    #[test]
+    fn test_combine_residue_sets() {
+        // x = 1 or 2 mod 4, and x = 0 or 3 mod 6. 1 mod 4 means x is odd, so it has to be 3 mod 6, and so on.
+        let (residues, modulus) = combine_residue_sets(&[1_i128, 2], 4, &[0, 3], 6);
+        assert_eq!(modulus, 12);
+        assert_eq!(residues, vec![6, 9]);
+
+        assert_eq!(combine_residue_sets(&[1_i128], 2, &[0], 4).0, vec![]);
+        assert_eq!(first_at_least(&residues, modulus, 10), Some(18));
+        assert_eq!(first_at_least(&residues, modulus, 9), Some(9));
+    }
+}
@@ -0,0 +1,206 @@
+// Day 10's field of pipes: finding the loop that runs through the animal's tile S.
+//
+// S hides which pipe is underneath it, so it's worked out from which neighbors connect back to it
+// (and, if there's a choice, which pair of them actually closes a loop).
+
+use std::collections::HashMap;
+use std::fmt;
+use lazy_static::lazy_static;
+
+use crate::polygon::{Direction, Point, Polygon};
+
+pub type Grid<T> = Vec<Vec<T>>;
+
+pub type Position = (usize, usize);
+
+const DIRECTIONS: [Direction; 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+lazy_static!{
+    static ref BYTE_TO_DIRECTIONS: HashMap<u8, (Direction, Direction)> = HashMap::from_iter(vec![
+        (b'|', ((0, 1), (0, -1))),
+        (b'-', ((1, 0), (-1, 0))),
+        (b'L', ((0, -1), (1, 0))),
+        (b'J', ((0, -1), (-1, 0))),
+        (b'7', ((-1, 0), (0, 1))),
+        (b'F', ((1, 0), (0, 1))),
+    ]);
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PipeError {
+    NoStart,
+    NoLoop,
+}
+
+impl fmt::Display for PipeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PipeError::NoStart => write!(f, "there's no S tile"),
+            PipeError::NoLoop => write!(f, "no two pipes next to S lead around a loop back to it"),
+        }
+    }
+}
+
+fn opposite(direction: Direction) -> Direction {
+    return (-direction.0, -direction.1);
+}
+
+fn connects(pipe: u8, direction: Direction) -> bool {
+    return BYTE_TO_DIRECTIONS.get(&pipe).map_or(false, |(d1, d2)| *d1 == direction || *d2 == direction);
+}
+
+fn pipe_with(d1: Direction, d2: Direction) -> u8 {
+    return BYTE_TO_DIRECTIONS.iter()
+        .find(|(_, (a, b))| (*a, *b) == (d1, d2) || (*a, *b) == (d2, d1))
+        .map(|(pipe, _)| *pipe)
+        .unwrap();
+}
+
+fn step(grid: &Grid<u8>, position: Position, direction: Direction) -> Option<Position> {
+    let (x, y) = position;
+    let new_x = x.checked_add_signed(direction.0 as isize)?;
+    let new_y = y.checked_add_signed(direction.1 as isize)?;
+    if new_y < grid.len() && new_x < grid[new_y].len() {
+        return Some((new_x, new_y));
+    }
+    return None;
+}
+
+// Follows the pipes from S, leaving in `direction`. If that leads back to S, returns the tiles on the way
+// (starting with S) and the direction it came back in.
+fn walk(grid: &Grid<u8>, start: Position, direction: Direction) -> Option<(Vec<Position>, Direction)> {
+    let mut tiles = vec![start];
+    let mut position = step(grid, start, direction)?;
+    let mut direction = direction;
+
+    while position != start {
+        let (x, y) = position;
+        let (d1, d2) = *BYTE_TO_DIRECTIONS.get(&grid[y][x])?;
+        direction = if d1 == opposite(direction) {
+            d2
+        } else if d2 == opposite(direction) {
+            d1
+        } else {
+            return None;
+        };
+
+        tiles.push(position);
+        position = step(grid, position, direction)?;
+    }
+
+    return Some((tiles, direction));
+}
+
+pub struct PipeLoop {
+    // The pipe hiding under S.
+    pub start_pipe: u8,
+    // Every tile around the loop in order, starting at S.
+    pub tiles: Vec<Position>,
+    // Every tile's pipe, with S replaced by start_pipe.
+    pipes: Vec<u8>,
+}
+
+impl PipeLoop {
+    pub fn find(grid: &Grid<u8>) -> Result<PipeLoop, PipeError> {
+        let start = grid.iter().enumerate()
+            .find_map(|(y, row)| row.iter().position(|c| *c == b'S').map(|x| (x, y)))
+            .ok_or(PipeError::NoStart)?;
+
+        // Neighbors whose pipe points back at S.
+        let connected: Vec<Direction> = DIRECTIONS.iter().copied()
+            .filter(|d| step(grid, start, *d).map_or(false, |(x, y)| connects(grid[y][x], opposite(*d))))
+            .collect();
+
+        for (i, first) in connected.iter().enumerate() {
+            let Some((tiles, last)) = walk(grid, start, *first) else { continue };
+
+            // The loop has to come back through one of the other connected neighbors.
+            if connected[i + 1..].contains(&opposite(last)) {
+                let start_pipe = pipe_with(*first, opposite(last));
+                let pipes = tiles.iter().map(|(x, y)| if (*x, *y) == start { start_pipe } else { grid[*y][*x] }).collect();
+                return Ok(PipeLoop { start_pipe: start_pipe, tiles: tiles, pipes: pipes });
+            }
+        }
+
+        return Err(PipeError::NoLoop);
+    }
+
+    // The same loop walked the other way round, still starting at S.
+    pub fn reversed(&self) -> PipeLoop {
+        let mut tiles = self.tiles.clone();
+        let mut pipes = self.pipes.clone();
+        tiles[1..].reverse();
+        pipes[1..].reverse();
+        return PipeLoop { start_pipe: self.start_pipe, tiles: tiles, pipes: pipes };
+    }
+
+    // The corners of the loop, in order. Straight pipes are just on the way between them.
+    pub fn vertices(&self) -> Vec<Point> {
+        return self.tiles.iter().zip(self.pipes.iter())
+            .filter(|(_, pipe)| **pipe != b'|' && **pipe != b'-')
+            .map(|((x, y), _)| (*x as i64, *y as i64))
+            .collect();
+    }
+
+    // Steps along the loop to the tile furthest from S, going whichever way round is shorter.
+    pub fn farthest_distance(&self) -> usize {
+        return self.tiles.len() / 2;
+    }
+
+    // Tiles enclosed by the loop.
+    pub fn enclosed_area(&self) -> i64 {
+        return Polygon::from_vertices(self.vertices()).interior_points();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> Grid<u8> {
+        return content.lines().map(|l| l.bytes().collect()).collect();
+    }
+
+    #[test]
+    fn test_farthest_distance() {
+        let pipe_loop = PipeLoop::find(&parse("-L|F7\n7S-7|\nL|7||\n-L-J|\nL|-JF")).unwrap();
+        assert_eq!(pipe_loop.start_pipe, b'F');
+        assert_eq!(pipe_loop.farthest_distance(), 4);
+        assert_eq!(pipe_loop.vertices(), vec![(1, 1), (3, 1), (3, 3), (1, 3)]);
+
+        let pipe_loop = PipeLoop::find(&parse("7-F7-\n.FJ|7\nSJLL7\n|F--J\nLJ.LJ")).unwrap();
+        assert_eq!(pipe_loop.farthest_distance(), 8);
+    }
+
+    #[test]
+    fn test_enclosed_area() {
+        let pipe_loop = PipeLoop::find(&parse("...........
+.S-------7.
+.|F-----7|.
+.||.....||.
+.||.....||.
+.|L-7.F-J|.
+.|..|.|..|.
+.L--J.L--J.
+...........")).unwrap();
+        assert_eq!(pipe_loop.enclosed_area(), 4);
+        assert_eq!(pipe_loop.reversed().enclosed_area(), 4);
+    }
+
+    #[test]
+    fn test_start_not_connected_below() {
+        // Nothing connects below S, and the pipes on the right don't lead anywhere.
+        let pipe_loop = PipeLoop::find(&parse(".....\n.F-7|\n.|.||\n.L-S.\n.....")).unwrap();
+        assert_eq!(pipe_loop.start_pipe, b'J');
+        assert_eq!(pipe_loop.tiles[..2], [(3, 3), (3, 2)]);
+        assert_eq!(pipe_loop.reversed().tiles[..2], [(3, 3), (2, 3)]);
+        assert_eq!(pipe_loop.farthest_distance(), 4);
+        assert_eq!(pipe_loop.enclosed_area(), 1);
+    }
+
+    #[test]
+    fn test_no_loop() {
+        assert_eq!(PipeLoop::find(&parse("...\n.S-\n...")).err(), Some(PipeError::NoLoop));
+        assert_eq!(PipeLoop::find(&parse("...")).err(), Some(PipeError::NoStart));
+    }
+}
@@ -0,0 +1,109 @@
+// Polygons on the integer lattice, like day 18's lagoon or day 10's pipe loop.
+//
+// The shoelace formula gives the area, and Pick's theorem (area = interior + boundary / 2 - 1) turns that into
+// a count of lattice points. Areas are kept doubled so they stay exact integers.
+
+use num::integer::gcd;
+
+pub type Point = (i64, i64);
+
+pub type Direction = (i64, i64);
+
+// Dig `distance` steps in `direction`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Instruction {
+    pub direction: Direction,
+    pub distance: i64,
+}
+
+pub struct Polygon {
+    // Corners in order around the polygon. The last one joins back up with the first.
+    pub vertices: Vec<Point>,
+}
+
+impl Polygon {
+    pub fn from_vertices(vertices: Vec<Point>) -> Polygon {
+        return Polygon { vertices: vertices };
+    }
+
+    // Follows the instructions from (0, 0). The last one should lead back there.
+    pub fn from_instructions(instructions: &[Instruction]) -> Polygon {
+        let mut vertices = vec![];
+        let (mut x, mut y) = (0, 0);
+        for instruction in instructions {
+            vertices.push((x, y));
+            let (dx, dy) = instruction.direction;
+            x += dx * instruction.distance;
+            y += dy * instruction.distance;
+        }
+        return Polygon { vertices: vertices };
+    }
+
+    fn edges(&self) -> impl Iterator<Item=(Point, Point)> + '_ {
+        let n = self.vertices.len();
+        return (0..n).map(move |i| (self.vertices[i], self.vertices[(i + 1) % n]));
+    }
+
+    // Shoelace formula. Positive when the vertices go clockwise on screen, where y points down.
+    pub fn twice_signed_area(&self) -> i64 {
+        return self.edges().map(|((x1, y1), (x2, y2))| x1 * y2 - x2 * y1).sum();
+    }
+
+    // Lattice points on the edges, counting each corner once.
+    pub fn boundary_points(&self) -> i64 {
+        return self.edges().map(|((x1, y1), (x2, y2))| gcd(x2 - x1, y2 - y1)).sum();
+    }
+
+    // Lattice points strictly inside, from Pick's theorem.
+    pub fn interior_points(&self) -> i64 {
+        return (self.twice_signed_area().abs() - self.boundary_points() + 2) / 2;
+    }
+
+    // The number of 1x1 squares dug out if each lattice point on or inside the polygon is a square, like day 18's
+    // trench and the lagoon it encloses.
+    pub fn area_with_boundary(&self) -> i64 {
+        return self.interior_points() + self.boundary_points();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_square() {
+        let polygon = Polygon::from_vertices(vec![(0, 0), (2, 0), (2, 2), (0, 2)]);
+        assert_eq!(polygon.twice_signed_area(), 8);
+        assert_eq!(polygon.boundary_points(), 8);
+        assert_eq!(polygon.interior_points(), 1);
+        assert_eq!(polygon.area_with_boundary(), 9);
+
+        let reversed = Polygon::from_vertices(vec![(0, 2), (2, 2), (2, 0), (0, 0)]);
+        assert_eq!(reversed.twice_signed_area(), -8);
+        assert_eq!(reversed.interior_points(), 1);
+    }
+
+    #[test]
+    fn test_triangle() {
+        // The long edge only goes through lattice points at its ends.
+        let polygon = Polygon::from_vertices(vec![(0, 0), (4, 0), (0, 3)]);
+        assert_eq!(polygon.twice_signed_area(), 12);
+        assert_eq!(polygon.boundary_points(), 8);
+        assert_eq!(polygon.interior_points(), 3);
+    }
+
+    #[test]
+    fn test_from_instructions() {
+        // The day 18 example.
+        let steps = [((1, 0), 6), ((0, 1), 5), ((-1, 0), 2), ((0, 1), 2), ((1, 0), 2), ((0, 1), 2), ((-1, 0), 5),
+            ((0, -1), 2), ((-1, 0), 1), ((0, -1), 2), ((1, 0), 2), ((0, -1), 3), ((-1, 0), 2), ((0, -1), 2)];
+        let instructions: Vec<Instruction> = steps.iter()
+            .map(|(direction, distance)| Instruction { direction: *direction, distance: *distance })
+            .collect();
+
+        let polygon = Polygon::from_instructions(&instructions);
+        assert_eq!(polygon.vertices[..3], [(0, 0), (6, 0), (6, 5)]);
+        assert_eq!(polygon.boundary_points(), 38);
+        assert_eq!(polygon.area_with_boundary(), 62);
+    }
+}
@@ -0,0 +1,169 @@
+// Disjoint sets over 0..n, with union by size and path compression.
+//
+// A UnionFind made with `with_rollback` can also undo unions back to a snapshot.
+// Path compression would rewrite parents that the undo log relies on, so it's switched off in that mode
+// (union by size alone still keeps trees O(log n) deep).
+
+pub struct UnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+    num_components: usize,
+    // Roots that were attached under another root, oldest first. None if rollback isn't enabled.
+    history: Option<Vec<usize>>,
+}
+
+impl UnionFind {
+    pub fn new(n: usize) -> UnionFind {
+        return UnionFind {
+            parent: (0..n).collect(),
+            size: vec![1; n],
+            num_components: n,
+            history: None,
+        };
+    }
+
+    pub fn with_rollback(n: usize) -> UnionFind {
+        let mut union_find = UnionFind::new(n);
+        union_find.history = Some(vec![]);
+        return union_find;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.parent.len();
+    }
+
+    pub fn find(&mut self, a: usize) -> usize {
+        let mut root = a;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+
+        if self.history.is_none() {
+            let mut x = a;
+            while self.parent[x] != root {
+                let next = self.parent[x];
+                self.parent[x] = root;
+                x = next;
+            }
+        }
+
+        return root;
+    }
+
+    // Returns false if a and b were already in the same set.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let mut root_a = self.find(a);
+        let mut root_b = self.find(b);
+
+        if root_a == root_b {
+            return false;
+        }
+
+        // Always hang the smaller tree under the larger one.
+        if self.size[root_a] < self.size[root_b] {
+            (root_a, root_b) = (root_b, root_a);
+        }
+
+        self.parent[root_b] = root_a;
+        self.size[root_a] += self.size[root_b];
+        self.num_components -= 1;
+
+        if let Some(history) = self.history.as_mut() {
+            history.push(root_b);
+        }
+
+        return true;
+    }
+
+    pub fn same(&mut self, a: usize, b: usize) -> bool {
+        return self.find(a) == self.find(b);
+    }
+
+    pub fn component_size(&mut self, a: usize) -> usize {
+        let root = self.find(a);
+        return self.size[root];
+    }
+
+    pub fn num_components(&self) -> usize {
+        return self.num_components;
+    }
+
+    // Each component as a sorted list of its members, ordered by smallest member.
+    pub fn components(&mut self) -> impl Iterator<Item=Vec<usize>> {
+        let mut root_to_component: Vec<Option<usize>> = vec![None; self.len()];
+        let mut components: Vec<Vec<usize>> = vec![];
+
+        for a in 0..self.len() {
+            let root = self.find(a);
+            match root_to_component[root] {
+                Some(i) => components[i].push(a),
+                None => {
+                    root_to_component[root] = Some(components.len());
+                    components.push(vec![a]);
+                }
+            }
+        }
+
+        return components.into_iter();
+    }
+
+    // Pass the result to `rollback` to undo every union made after this point.
+    pub fn snapshot(&self) -> usize {
+        return self.history.as_ref().expect("UnionFind was not created with_rollback").len();
+    }
+
+    pub fn rollback(&mut self, snapshot: usize) {
+        let history = self.history.as_mut().expect("UnionFind was not created with_rollback");
+
+        while history.len() > snapshot {
+            let child = history.pop().unwrap();
+            let root = self.parent[child];
+
+            self.size[root] -= self.size[child];
+            self.parent[child] = child;
+            self.num_components += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_union() {
+        let mut union_find = UnionFind::new(6);
+        assert!(union_find.union(0, 1));
+        assert!(union_find.union(2, 1));
+        assert!(!union_find.union(0, 2));
+        assert!(union_find.union(4, 5));
+
+        assert!(union_find.same(0, 2));
+        assert!(!union_find.same(0, 4));
+        assert_eq!(union_find.component_size(1), 3);
+        assert_eq!(union_find.component_size(3), 1);
+        assert_eq!(union_find.num_components(), 3);
+
+        let components: Vec<_> = union_find.components().collect();
+        assert_eq!(components, vec![vec![0, 1, 2], vec![3], vec![4, 5]]);
+    }
+
+    #[test]
+    fn test_rollback() {
+        let mut union_find = UnionFind::with_rollback(5);
+        union_find.union(0, 1);
+
+        let snapshot = union_find.snapshot();
+        union_find.union(1, 2);
+        union_find.union(3, 4);
+        union_find.union(4, 0);
+        assert_eq!(union_find.num_components(), 1);
+
+        union_find.rollback(snapshot);
+        assert_eq!(union_find.num_components(), 4);
+        assert!(union_find.same(0, 1));
+        assert!(!union_find.same(1, 2));
+        assert!(!union_find.same(3, 4));
+        assert_eq!(union_find.component_size(0), 2);
+    }
+}